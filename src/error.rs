@@ -13,15 +13,33 @@ pub enum CsvColError {
     #[error("Column failed to parse number on row {0} and field {1}: {2}")]
     ColumnParse(usize, usize, #[source] ColumnParseError),
 
+    #[error("Row {0} has {1} fields, expected {2}")]
+    RaggedRow(usize, usize, usize),
+
     #[error("DDSketch error: {0}")]
     DDSketch(#[from] DDSketchError),
 
     #[error("Can't parse filter: {0}")]
     Filter(String),
 
+    #[error("Filter references unknown column '{0}'")]
+    UnknownFilterColumn(String),
+
+    #[error("Filter condition combines columns '{0}' and '{1}', which needs --filter-mode row")]
+    CrossColumnFilter(String, String),
+
     #[error("Can't parse CSV")]
     CsvParse(#[from] csv::Error),
 
+    #[error("Failed to spill column values to disk: {0}")]
+    Spill(#[source] std::io::Error),
+
+    #[error("Failed to write rejected row: {0}")]
+    Reject(#[source] csv::Error),
+
+    #[error("Input is {0} compressed, rebuild with the `{0}` feature to read it")]
+    UnsupportedCompression(&'static str),
+
     #[error("Thread paniced")]
     ThreadPanic,
 }
@@ -1,11 +1,13 @@
-use std::str::FromStr;
+use std::{cmp::Ordering, fmt, str::FromStr};
 
-use crate::Result;
+use crate::{CsvColError, Number, Result};
 
-use crate::CsvColError;
+/// Words with a meaning of their own, columns named like them have to be
+/// quoted with backticks.
+const KEYWORDS: [&str; 7] = ["AND", "OR", "NOT", "IN", "BETWEEN", "IS", "NULL"];
 
-#[derive(Clone, Debug)]
-enum Operator {
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operator {
     Gt,
     Ge,
     Lt,
@@ -15,64 +17,870 @@ enum Operator {
 }
 
 impl Operator {
-    fn try_parse(value: &str) -> Result<Self> {
-        let result = match value.trim() {
-            ">=" => Self::Ge,
-            "<=" => Self::Le,
-            "==" => Self::Eq,
-            "!=" => Self::Ne,
-            "<" => Self::Lt,
-            ">" => Self::Gt,
-            _ => return Err(CsvColError::Filter("bad operator".to_string())),
+    fn matches(self, ordering: Ordering) -> bool {
+        match self {
+            Operator::Gt => ordering.is_gt(),
+            Operator::Ge => ordering.is_ge(),
+            Operator::Lt => ordering.is_lt(),
+            Operator::Le => ordering.is_le(),
+            Operator::Ne => ordering.is_ne(),
+            Operator::Eq => ordering.is_eq(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArithmeticOperator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl ArithmeticOperator {
+    /// Result of the operation, integers stay integers unless they overflow
+    /// or are divided. `None` on division by zero.
+    fn apply(self, left: Number, right: Number) -> Option<Number> {
+        if let (Number::Int(left), Number::Int(right)) = (left, right) {
+            let exact = match self {
+                ArithmeticOperator::Add => left.checked_add(right),
+                ArithmeticOperator::Sub => left.checked_sub(right),
+                ArithmeticOperator::Mul => left.checked_mul(right),
+                ArithmeticOperator::Div => None,
+            };
+            if let Some(value) = exact {
+                return Some(Number::Int(value));
+            }
+        }
+
+        let (left, right) = (left.as_f64(), right.as_f64());
+        let value = match self {
+            ArithmeticOperator::Add => left + right,
+            ArithmeticOperator::Sub => left - right,
+            ArithmeticOperator::Mul => left * right,
+            ArithmeticOperator::Div if right == 0. => return None,
+            ArithmeticOperator::Div => left / right,
         };
-        Ok(result)
+        Some(Number::Float(value))
     }
 }
 
-#[derive(Clone, Debug)]
+fn negate(number: Number) -> Number {
+    match number {
+        Number::Int(value) => value
+            .checked_neg()
+            .map_or(Number::Float(-(value as f64)), Number::Int),
+        Number::Float(value) => Number::Float(-value),
+    }
+}
+
+/// Whether `reference` points at the column `name` at zero-based `index`,
+/// either by name or by position written as `#3`.
+pub(crate) fn refers_to(reference: &str, index: usize, name: &str) -> bool {
+    reference == name
+        || reference
+            .strip_prefix('#')
+            .and_then(|position| position.parse::<usize>().ok())
+            == Some(index)
+}
+
+/// Column reference, literal or arithmetic on them in a filter.
+#[derive(Clone, Debug, PartialEq)]
 pub enum Operand {
+    /// Column referenced by name or by zero-based index like `#3`.
     Column(String),
-    Number(i64),
+    /// The column a filter was bound to with [`Expression::for_column`].
+    Current,
+    /// Column resolved to its zero-based index in a record with
+    /// [`Expression::resolve`].
+    Field(usize),
+    Number(Number),
+    Text(String),
+    Negate(Box<Operand>),
+    Arithmetic(Box<Operand>, ArithmeticOperator, Box<Operand>),
 }
 
-#[derive(Clone, Debug)]
-pub struct Expression {
-    left: Operand,
-    operator: Operator,
-    right: Operand,
+impl Operand {
+    /// Type of the operand, `None` for columns whose type is only known once
+    /// the data is read.
+    fn value_type(&self) -> Option<&'static str> {
+        match self {
+            Operand::Column(_) | Operand::Current | Operand::Field(_) => None,
+            Operand::Number(_) | Operand::Negate(_) | Operand::Arithmetic(..) => Some("number"),
+            Operand::Text(_) => Some("string"),
+        }
+    }
+
+    fn is_literal(&self) -> bool {
+        matches!(self, Operand::Number(_) | Operand::Text(_))
+    }
+
+    /// First column reference or literal matching `predicate`.
+    fn find_leaf(&self, predicate: &impl Fn(&Operand) -> bool) -> Option<&Operand> {
+        match self {
+            Operand::Negate(operand) => operand.find_leaf(predicate),
+            Operand::Arithmetic(left, _, right) => left
+                .find_leaf(predicate)
+                .or_else(|| right.find_leaf(predicate)),
+            leaf => Some(leaf).filter(|leaf| predicate(leaf)),
+        }
+    }
+
+    /// Copy with every column reference and literal replaced by `map`.
+    fn map_leaves(&self, map: &impl Fn(&Operand) -> Operand) -> Self {
+        match self {
+            Operand::Negate(operand) => Operand::Negate(Box::new(operand.map_leaves(map))),
+            Operand::Arithmetic(left, operator, right) => Operand::Arithmetic(
+                Box::new(left.map_leaves(map)),
+                *operator,
+                Box::new(right.map_leaves(map)),
+            ),
+            leaf => map(leaf),
+        }
+    }
+
+    /// Value of the operand, `column` gives the value of column references.
+    /// Arithmetic on a null or non-numeric value is null.
+    fn value<'a>(&'a self, column: &impl Fn(&'a Operand) -> Value<'a>) -> Value<'a> {
+        let number = |operand: &'a Operand| operand.value(column).number;
+        match self {
+            Operand::Number(number) => Value::number(*number),
+            Operand::Text(text) => Value::text(text),
+            Operand::Negate(operand) => {
+                number(operand).map_or(Value::NULL, |n| Value::number(negate(n)))
+            }
+            Operand::Arithmetic(left, operator, right) => number(left)
+                .zip(number(right))
+                .and_then(|(left, right)| operator.apply(left, right))
+                .map_or(Value::NULL, Value::number),
+            reference => column(reference),
+        }
+    }
+}
+
+/// Value of an [`Operand`] while a filter is evaluated.
+///
+/// A CSV field has both its text and, if it parses, its number, so it
+/// compares as a number against numbers and as text against strings.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Value<'a> {
+    pub text: Option<&'a str>,
+    pub number: Option<Number>,
+    /// A string literal rather than a field, numeric fields compare with it
+    /// as text.
+    pub is_string: bool,
+}
+
+impl<'a> Value<'a> {
+    pub const NULL: Self = Self {
+        text: None,
+        number: None,
+        is_string: false,
+    };
+
+    pub fn number(number: Number) -> Self {
+        Self {
+            number: Some(number),
+            ..Self::NULL
+        }
+    }
+
+    pub fn text(text: &'a str) -> Self {
+        Self {
+            text: Some(text),
+            is_string: true,
+            ..Self::NULL
+        }
+    }
+
+    /// Ordering of two values, `None` if either is null or they have
+    /// different types.
+    fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self.number, other.number, self.text, other.text) {
+            (Some(left), Some(right), _, _) => left.partial_cmp(&right),
+            // a number against a field that isn't one, like `13` and `x1`
+            (Some(_), None, ..) | (None, Some(_), ..) if !self.is_string && !other.is_string => {
+                None
+            }
+            (_, _, Some(left), Some(right)) => Some(left.cmp(right)),
+            _ => None,
+        }
+    }
+}
+
+/// Parsed filter expression.
+///
+/// Conditions follow SQL semantics: comparing against a null or missing
+/// value is unknown rather than false, and a value passes the filter only
+/// if the whole expression is true.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expression {
+    Compare(Operand, Operator, Operand),
+    In {
+        operand: Operand,
+        list: Vec<Operand>,
+        negated: bool,
+    },
+    Between {
+        operand: Operand,
+        low: Operand,
+        high: Operand,
+        negated: bool,
+    },
+    IsNull {
+        operand: Operand,
+        negated: bool,
+    },
+    Not(Box<Expression>),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
 }
 
 impl Expression {
-    pub fn check_by_name(&self, column_name: &str) -> bool {
-        if let Operand::Column(value) = &self.left
-            && value == column_name
-        {
-            true
-        } else if let Operand::Column(value) = &self.right
-            && value == column_name
-        {
-            true
-        } else {
-            false
+    /// First column reference or literal matching `predicate`.
+    fn find_leaf(&self, predicate: &impl Fn(&Operand) -> bool) -> Option<&Operand> {
+        match self {
+            Expression::Compare(left, _, right) => left
+                .find_leaf(predicate)
+                .or_else(|| right.find_leaf(predicate)),
+            Expression::In { operand, list, .. } => std::iter::once(operand)
+                .chain(list)
+                .find_map(|operand| operand.find_leaf(predicate)),
+            Expression::Between {
+                operand, low, high, ..
+            } => [operand, low, high]
+                .into_iter()
+                .find_map(|operand| operand.find_leaf(predicate)),
+            Expression::IsNull { operand, .. } => operand.find_leaf(predicate),
+            Expression::Not(expression) => expression.find_leaf(predicate),
+            Expression::And(left, right) | Expression::Or(left, right) => left
+                .find_leaf(predicate)
+                .or_else(|| right.find_leaf(predicate)),
         }
     }
 
-    pub fn unchacked_validate(&self, value: &i64) -> bool {
-        let (left, right) = if let Operand::Number(operand) = &self.left {
-            (operand, value)
-        } else if let Operand::Number(operand) = &self.right {
-            (value, operand)
+    /// Copy with every column reference and literal replaced by `map`.
+    fn map_leaves(&self, map: &impl Fn(&Operand) -> Operand) -> Self {
+        match self {
+            Expression::Compare(left, operator, right) => {
+                Expression::Compare(left.map_leaves(map), *operator, right.map_leaves(map))
+            }
+            Expression::In {
+                operand,
+                list,
+                negated,
+            } => Expression::In {
+                operand: operand.map_leaves(map),
+                list: list.iter().map(|item| item.map_leaves(map)).collect(),
+                negated: *negated,
+            },
+            Expression::Between {
+                operand,
+                low,
+                high,
+                negated,
+            } => Expression::Between {
+                operand: operand.map_leaves(map),
+                low: low.map_leaves(map),
+                high: high.map_leaves(map),
+                negated: *negated,
+            },
+            Expression::IsNull { operand, negated } => Expression::IsNull {
+                operand: operand.map_leaves(map),
+                negated: *negated,
+            },
+            Expression::Not(expression) => Expression::Not(Box::new(expression.map_leaves(map))),
+            Expression::And(left, right) => Expression::And(
+                Box::new(left.map_leaves(map)),
+                Box::new(right.map_leaves(map)),
+            ),
+            Expression::Or(left, right) => Expression::Or(
+                Box::new(left.map_leaves(map)),
+                Box::new(right.map_leaves(map)),
+            ),
+        }
+    }
+
+    /// Three-valued result of the expression, `None` when it is unknown.
+    /// `column` gives the value of column references.
+    fn evaluate<'a>(&'a self, column: &impl Fn(&'a Operand) -> Value<'a>) -> Option<bool> {
+        match self {
+            Expression::Compare(left, operator, right) => left
+                .value(column)
+                .compare(&right.value(column))
+                .map(|ordering| operator.matches(ordering)),
+            Expression::In {
+                operand,
+                list,
+                negated,
+            } => {
+                let operand = operand.value(column);
+                if operand == Value::NULL {
+                    return None;
+                }
+                let found = list
+                    .iter()
+                    .any(|item| operand.compare(&item.value(column)) == Some(Ordering::Equal));
+                Some(found != *negated)
+            }
+            Expression::Between {
+                operand,
+                low,
+                high,
+                negated,
+            } => {
+                let operand = operand.value(column);
+                let above = operand.compare(&low.value(column)).map(Ordering::is_ge);
+                let below = operand.compare(&high.value(column)).map(Ordering::is_le);
+                and(above, below).map(|between| between != *negated)
+            }
+            Expression::IsNull { operand, negated } => {
+                Some((operand.value(column) == Value::NULL) != *negated)
+            }
+            Expression::Not(expression) => expression.evaluate(column).map(|result| !result),
+            Expression::And(left, right) => match left.evaluate(column) {
+                Some(false) => Some(false),
+                left => and(left, right.evaluate(column)),
+            },
+            Expression::Or(left, right) => match left.evaluate(column) {
+                Some(true) => Some(true),
+                left => or(left, right.evaluate(column)),
+            },
+        }
+    }
+
+    /// Whether the expression applies to the column `column_name` at
+    /// zero-based `index`.
+    pub fn check_column(&self, index: usize, column_name: &str) -> bool {
+        self.find_leaf(
+            &|operand| matches!(operand, Operand::Column(reference) if refers_to(reference, index, column_name)),
+        )
+        .is_some()
+    }
+
+    /// Binds the expression to the column `column_name` at zero-based
+    /// `index`, references to it take the value given to
+    /// [`Expression::matches_value`].
+    pub fn for_column(&self, index: usize, column_name: &str) -> Self {
+        self.map_leaves(&|operand| match operand {
+            Operand::Column(reference) if refers_to(reference, index, column_name) => {
+                Operand::Current
+            }
+            operand => operand.clone(),
+        })
+    }
+
+    /// Whether `value` of the bound column passes the filter. Other columns
+    /// are unknown, so conditions on them never hold on their own, see
+    /// [`Expression::cross_column`] for conditions mixing columns.
+    pub fn matches_value(&self, value: &Number) -> bool {
+        self.evaluate(&|operand| match operand {
+            Operand::Current => Value::number(*value),
+            _ => Value::NULL,
+        }) == Some(true)
+    }
+
+    /// Resolves column references to their index in `headers`, for
+    /// [`Expression::matches_row`].
+    ///
+    /// # Errors
+    /// Returns [`CsvColError::UnknownFilterColumn`] if a referenced column
+    /// isn't in `headers`.
+    pub fn resolve(&self, headers: &[String]) -> Result<Self> {
+        let position = |reference: &str| {
+            headers
+                .iter()
+                .enumerate()
+                .position(|(index, header)| refers_to(reference, index, header))
+        };
+        let unknown = self.find_leaf(
+            &|operand| matches!(operand, Operand::Column(reference) if position(reference).is_none()),
+        );
+        if let Some(Operand::Column(reference)) = unknown {
+            return Err(CsvColError::UnknownFilterColumn(reference.clone()));
+        }
+
+        Ok(self.map_leaves(&|operand| match operand {
+            Operand::Column(reference) => Operand::Field(position(reference).unwrap_or_default()),
+            operand => operand.clone(),
+        }))
+    }
+
+    /// Indices of two different fields referenced by a single condition of
+    /// the resolved filter, like `a > b` or `a + b IS NULL`. Such a condition
+    /// can't hold when columns are filtered one at a time.
+    pub(crate) fn cross_column(&self) -> Option<(usize, usize)> {
+        match self {
+            Expression::Not(expression) => expression.cross_column(),
+            Expression::And(left, right) | Expression::Or(left, right) => {
+                left.cross_column().or_else(|| right.cross_column())
+            }
+            condition => {
+                let Some(&Operand::Field(first)) =
+                    condition.find_leaf(&|operand| matches!(operand, Operand::Field(_)))
+                else {
+                    return None;
+                };
+                match condition.find_leaf(
+                    &|operand| matches!(operand, Operand::Field(index) if *index != first),
+                ) {
+                    Some(&Operand::Field(second)) => Some((first, second)),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// Whether a row passes the resolved filter, `field` gives the value of
+    /// the field at an index.
+    pub(crate) fn matches_row<'a>(&'a self, field: impl Fn(usize) -> Value<'a>) -> bool {
+        self.evaluate(&|operand| match operand {
+            Operand::Field(index) => field(*index),
+            _ => Value::NULL,
+        }) == Some(true)
+    }
+}
+
+/// Kleene conjunction of two unknown-able conditions.
+fn and(left: Option<bool>, right: Option<bool>) -> Option<bool> {
+    match (left, right) {
+        (Some(false), _) | (_, Some(false)) => Some(false),
+        (Some(true), Some(true)) => Some(true),
+        _ => None,
+    }
+}
+
+/// Kleene disjunction of two unknown-able conditions.
+fn or(left: Option<bool>, right: Option<bool>) -> Option<bool> {
+    match (left, right) {
+        (Some(true), _) | (_, Some(true)) => Some(true),
+        (Some(false), Some(false)) => Some(false),
+        _ => None,
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    /// Column name or keyword.
+    Word(String),
+    /// Column name quoted with backticks, never a keyword.
+    Quoted(String),
+    Number(Number),
+    Text(String),
+    Operator(Operator),
+    Arithmetic(ArithmeticOperator),
+    And,
+    Or,
+    Not,
+    LeftParen,
+    RightParen,
+    Comma,
+    End,
+}
+
+/// Error pointing at the character at byte `position` of `input`.
+fn error(input: &str, position: usize, message: impl fmt::Display) -> CsvColError {
+    let column = input[..position].chars().count();
+    CsvColError::Filter(format!(
+        "{message} at character {}\n    {input}\n    {}^",
+        column + 1,
+        " ".repeat(column)
+    ))
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '#' | '.')
+}
+
+/// Contents of the string starting with `quote` at byte `start`, where a
+/// doubled quote stands for the quote itself, and the bytes it spans.
+fn quoted(input: &str, start: usize, quote: char) -> Result<(String, usize)> {
+    let mut text = String::new();
+    let mut chars = input[start + 1..].char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+        if c != quote {
+            text.push(c);
+        } else if chars.next_if(|&(_, next)| next == quote).is_some() {
+            text.push(quote);
         } else {
-            panic!("Expression was badly constructed");
+            return Ok((text, offset + 2));
+        }
+    }
+    Err(error(input, start, "unterminated quote"))
+}
+
+/// Splits `input` into tokens along with their byte position, whitespace
+/// between tokens is ignored.
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>> {
+    let mut tokens = Vec::new();
+    let mut position = 0;
+    while let Some(c) = input[position..].chars().next() {
+        if c.is_whitespace() {
+            position += c.len_utf8();
+            continue;
+        }
+
+        let rest = &input[position..];
+        let (token, length) = match (c, rest.get(..2).unwrap_or(rest)) {
+            (_, "&&") => (Token::And, 2),
+            (_, "||") => (Token::Or, 2),
+            (_, "==") => (Token::Operator(Operator::Eq), 2),
+            (_, "!=" | "<>") => (Token::Operator(Operator::Ne), 2),
+            (_, "<=") => (Token::Operator(Operator::Le), 2),
+            (_, ">=") => (Token::Operator(Operator::Ge), 2),
+            ('=', _) => (Token::Operator(Operator::Eq), 1),
+            ('<', _) => (Token::Operator(Operator::Lt), 1),
+            ('>', _) => (Token::Operator(Operator::Gt), 1),
+            ('!', _) => (Token::Not, 1),
+            ('+', _) => (Token::Arithmetic(ArithmeticOperator::Add), 1),
+            ('-', _) => (Token::Arithmetic(ArithmeticOperator::Sub), 1),
+            ('*', _) => (Token::Arithmetic(ArithmeticOperator::Mul), 1),
+            ('/', _) => (Token::Arithmetic(ArithmeticOperator::Div), 1),
+            ('(', _) => (Token::LeftParen, 1),
+            (')', _) => (Token::RightParen, 1),
+            (',', _) => (Token::Comma, 1),
+            ('\'' | '"', _) => {
+                let (text, length) = quoted(input, position, c)?;
+                (Token::Text(text), length)
+            }
+            ('`', _) => {
+                let (name, length) = quoted(input, position, c)?;
+                (Token::Quoted(name), length)
+            }
+            (c, _) if c.is_ascii_digit() || c == '.' => {
+                let length = number_length(rest);
+                let text = &rest[..length];
+                let number = text
+                    .parse::<i64>()
+                    .map(Number::Int)
+                    .or_else(|_| text.parse::<f64>().map(Number::Float))
+                    .map_err(|_| error(input, position, format!("invalid number '{text}'")))?;
+                (Token::Number(number), length)
+            }
+            (c, _) if is_word_char(c) => {
+                let length = word_length(rest);
+                (Token::Word(rest[..length].to_string()), length)
+            }
+            (c, _) => {
+                return Err(error(
+                    input,
+                    position,
+                    format!("unexpected character '{c}'"),
+                ));
+            }
         };
+        tokens.push((position, token));
+        position += length;
+    }
+    tokens.push((input.len(), Token::End));
+    Ok(tokens)
+}
+
+/// Bytes of the column name at the start of `rest`. A `-` directly between
+/// word characters belongs to the name, so `unit-price` is a column while
+/// `unit - price` is a subtraction.
+fn word_length(rest: &str) -> usize {
+    let mut chars = rest.char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+        let joins = c == '-' && chars.peek().is_some_and(|&(_, next)| is_word_char(next));
+        if !(is_word_char(c) || joins) {
+            return offset;
+        }
+    }
+    rest.len()
+}
+
+/// Bytes of the number at the start of `rest`, including any letters
+/// directly following it so `12abc` is reported as a whole.
+fn number_length(rest: &str) -> usize {
+    let bytes = rest.as_bytes();
+    let mut length = 0;
+    while let Some(&byte) = bytes.get(length) {
+        length += 1;
+        if matches!(byte, b'e' | b'E') && matches!(bytes.get(length), Some(b'+' | b'-')) {
+            length += 1;
+        } else if !(byte.is_ascii_alphanumeric() || matches!(byte, b'.' | b'_')) {
+            return length - 1;
+        }
+    }
+    length
+}
+
+/// Recursive descent parser, from the loosest binding operator down:
+///
+/// ```text
+/// or        := and (("||" | OR) and)*
+/// and       := unary (("&&" | AND) unary)*
+/// unary     := ("!" | NOT) unary | "(" or ")" | predicate
+/// predicate := operand ( comparison operand
+///                      | [NOT] IN "(" literal ("," literal)* ")"
+///                      | [NOT] BETWEEN operand AND operand
+///                      | IS [NOT] NULL )
+/// operand   := term (("+" | "-") term)*
+/// term      := factor (("*" | "/") factor)*
+/// factor    := "-" factor | "(" operand ")" | column | number | string
+/// ```
+///
+/// Types are checked while parsing, so comparing or computing with values
+/// of different types is an error rather than a filter that never holds.
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.position].1
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.position].1.clone();
+        if token != Token::End {
+            self.position += 1;
+        }
+        token
+    }
+
+    fn error_at(&self, position: usize, message: impl fmt::Display) -> CsvColError {
+        error(self.input, self.tokens[position].0, message)
+    }
+
+    fn error(&self, message: impl fmt::Display) -> CsvColError {
+        self.error_at(self.position, message)
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Token::Word(word) if word.eq_ignore_ascii_case(keyword))
+    }
 
-        match self.operator {
-            Operator::Gt => left.gt(right),
-            Operator::Ge => left.ge(right),
-            Operator::Lt => left.lt(right),
-            Operator::Le => left.le(right),
-            Operator::Ne => left.ne(right),
-            Operator::Eq => left.eq(right),
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = self.peek() == token;
+        if found {
+            self.next();
+        }
+        found
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.next();
+        }
+        found
+    }
+
+    fn expect(&mut self, token: &Token, expected: &str) -> Result<()> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected {expected}")))
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected {keyword}")))
+        }
+    }
+
+    fn or(&mut self) -> Result<Expression> {
+        let mut expression = self.and()?;
+        while self.eat(&Token::Or) || self.eat_keyword("OR") {
+            expression = Expression::Or(Box::new(expression), Box::new(self.and()?));
+        }
+        Ok(expression)
+    }
+
+    fn and(&mut self) -> Result<Expression> {
+        let mut expression = self.unary()?;
+        while self.eat(&Token::And) || self.eat_keyword("AND") {
+            expression = Expression::And(Box::new(expression), Box::new(self.unary()?));
+        }
+        Ok(expression)
+    }
+
+    fn unary(&mut self) -> Result<Expression> {
+        if self.eat(&Token::Not) || self.eat_keyword("NOT") {
+            Ok(Expression::Not(Box::new(self.unary()?)))
+        } else if *self.peek() == Token::LeftParen {
+            // "(" opens either a condition or an operand like "(a + b) > 3",
+            // the latter is tried first. When neither parses, the error of
+            // the one that got further is the one the user meant
+            let start = self.position;
+            let operand_error = match self.predicate() {
+                Ok(expression) => return Ok(expression),
+                Err(e) => (self.position, e),
+            };
+            self.position = start + 1;
+            let condition = self.or().and_then(|expression| {
+                self.expect(&Token::RightParen, "')'")?;
+                Ok(expression)
+            });
+            match condition {
+                Err(_) if operand_error.0 > self.position => Err(operand_error.1),
+                condition => condition,
+            }
+        } else {
+            self.predicate()
+        }
+    }
+
+    fn predicate(&mut self) -> Result<Expression> {
+        let operand = self.operand()?;
+
+        if let Token::Operator(operator) = *self.peek() {
+            let position = self.position;
+            self.next();
+            let right = self.operand()?;
+            self.check_comparable(position, &operand, &right)?;
+            return Ok(Expression::Compare(operand, operator, right));
+        }
+
+        if self.eat_keyword("IS") {
+            let negated = self.eat_keyword("NOT");
+            self.expect_keyword("NULL")?;
+            return Ok(Expression::IsNull { operand, negated });
+        }
+
+        let negated = self.eat_keyword("NOT");
+        if self.eat_keyword("IN") {
+            self.expect(&Token::LeftParen, "'('")?;
+            let mut list = Vec::new();
+            loop {
+                let position = self.position;
+                let item = self.operand()?;
+                if !item.is_literal() {
+                    return Err(self.error_at(position, "expected a number or string"));
+                }
+                self.check_comparable(position, &operand, &item)?;
+                if let Some(first) = list.first() {
+                    self.check_comparable(position, first, &item)?;
+                }
+                list.push(item);
+                if !self.eat(&Token::Comma) {
+                    break;
+                }
+            }
+            self.expect(&Token::RightParen, "',' or ')'")?;
+            Ok(Expression::In {
+                operand,
+                list,
+                negated,
+            })
+        } else if self.eat_keyword("BETWEEN") {
+            let position = self.position;
+            let low = self.operand()?;
+            self.check_comparable(position, &operand, &low)?;
+            self.expect_keyword("AND")?;
+            let position = self.position;
+            let high = self.operand()?;
+            self.check_comparable(position, &operand, &high)?;
+            self.check_comparable(position, &low, &high)?;
+            Ok(Expression::Between {
+                operand,
+                low,
+                high,
+                negated,
+            })
+        } else if negated {
+            Err(self.error("expected IN or BETWEEN"))
+        } else {
+            Err(self.error("expected a comparison, IN, BETWEEN or IS NULL"))
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand> {
+        let mut operand = self.term()?;
+        while let Token::Arithmetic(
+            operator @ (ArithmeticOperator::Add | ArithmeticOperator::Sub),
+        ) = *self.peek()
+        {
+            let position = self.position;
+            self.next();
+            let right = self.term()?;
+            operand = self.arithmetic(position, operand, operator, right)?;
+        }
+        Ok(operand)
+    }
+
+    fn term(&mut self) -> Result<Operand> {
+        let mut operand = self.factor()?;
+        while let Token::Arithmetic(
+            operator @ (ArithmeticOperator::Mul | ArithmeticOperator::Div),
+        ) = *self.peek()
+        {
+            let position = self.position;
+            self.next();
+            let right = self.factor()?;
+            operand = self.arithmetic(position, operand, operator, right)?;
+        }
+        Ok(operand)
+    }
+
+    fn factor(&mut self) -> Result<Operand> {
+        let position = self.position;
+        match self.next() {
+            Token::Number(number) => Ok(Operand::Number(number)),
+            Token::Text(text) => Ok(Operand::Text(text)),
+            Token::Quoted(name) => Ok(Operand::Column(name)),
+            Token::Word(word)
+                if KEYWORDS
+                    .iter()
+                    .any(|keyword| word.eq_ignore_ascii_case(keyword)) =>
+            {
+                Err(self.error_at(
+                    position,
+                    format!("unexpected keyword {word}, quote column names with backticks"),
+                ))
+            }
+            Token::Word(name) => Ok(Operand::Column(name)),
+            Token::Arithmetic(ArithmeticOperator::Sub) => match self.factor()? {
+                Operand::Number(number) => Ok(Operand::Number(negate(number))),
+                Operand::Text(_) => Err(self.error_at(position, "can't negate a string")),
+                operand => Ok(Operand::Negate(Box::new(operand))),
+            },
+            Token::LeftParen => {
+                let operand = self.operand()?;
+                self.expect(&Token::RightParen, "')'")?;
+                Ok(operand)
+            }
+            _ => Err(self.error_at(position, "expected a column, number or string")),
+        }
+    }
+
+    /// Arithmetic on `left` and `right`, which can't be strings.
+    fn arithmetic(
+        &self,
+        position: usize,
+        left: Operand,
+        operator: ArithmeticOperator,
+        right: Operand,
+    ) -> Result<Operand> {
+        if [&left, &right]
+            .iter()
+            .any(|operand| operand.value_type() == Some("string"))
+        {
+            return Err(self.error_at(position, "can't do arithmetic on a string"));
+        }
+        Ok(Operand::Arithmetic(
+            Box::new(left),
+            operator,
+            Box::new(right),
+        ))
+    }
+
+    /// Rejects comparisons that can never hold, between values of different
+    /// types. Columns compare with anything, their type is only known per
+    /// value.
+    fn check_comparable(&self, position: usize, left: &Operand, right: &Operand) -> Result<()> {
+        match (left.value_type(), right.value_type()) {
+            (Some(left), Some(right)) if left != right => {
+                Err(self.error_at(position, format!("can't compare {left} with {right}")))
+            }
+            _ => Ok(()),
         }
     }
 }
@@ -81,33 +889,297 @@ impl FromStr for Expression {
     type Err = CsvColError;
 
     fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
-        let items: Vec<&str> = value.split(' ').collect();
-        if items.len() != 3 {
-            return Err(CsvColError::Filter(
-                "Filter should have 2 operands and 1 operator".to_string(),
-            ));
+        let mut parser = Parser {
+            input: value,
+            tokens: tokenize(value)?,
+            position: 0,
+        };
+        let expression = parser.or()?;
+        if *parser.peek() != Token::End {
+            return Err(parser.error("expected '&&', '||' or end of filter"));
         }
+        Ok(expression)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Number::{Float, Int};
+
+    use super::*;
+
+    fn column(name: &str) -> Operand {
+        Operand::Column(name.to_string())
+    }
+
+    fn parse(filter: &str) -> Expression {
+        Expression::from_str(filter).unwrap()
+    }
+
+    fn parse_error(filter: &str) -> String {
+        Expression::from_str(filter).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_parse_whitespace_insensitive() {
+        let expected = Expression::Compare(column("value"), Operator::Ge, Operand::Number(Int(10)));
+        assert_eq!(parse("value >= 10"), expected);
+        assert_eq!(parse("value>=10"), expected);
+        assert_eq!(parse("  value\t>=  10 "), expected);
+        assert_eq!(
+            parse("-1.5<`my col`"),
+            Expression::Compare(Operand::Number(Float(-1.5)), Operator::Lt, column("my col"))
+        );
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        let a = || Box::new(parse("a > 1"));
+        let b = || Box::new(parse("b > 2"));
+        let c = || Box::new(parse("c > 3"));
 
-        let operator = Operator::try_parse(items[1])?;
+        assert_eq!(
+            parse("a > 1 || b > 2 && c > 3"),
+            Expression::Or(a(), Box::new(Expression::And(b(), c())))
+        );
+        assert_eq!(
+            parse("(a > 1 || b > 2) && !c > 3"),
+            Expression::And(
+                Box::new(Expression::Or(a(), b())),
+                Box::new(Expression::Not(c()))
+            )
+        );
+        assert_eq!(parse("a > 1 and not b > 2"), parse("a>1&&!b>2"));
+    }
 
-        let expression = if let Ok(value) = items[0].parse::<i64>() {
-            Self {
-                left: Operand::Number(value),
-                operator,
-                right: Operand::Column(items[2].to_string()),
+    #[test]
+    fn test_parse_predicates() {
+        assert_eq!(
+            parse("status not in ('paid', \"it''s\")"),
+            Expression::In {
+                operand: column("status"),
+                list: vec![
+                    Operand::Text("paid".to_string()),
+                    Operand::Text("it''s".to_string())
+                ],
+                negated: true,
             }
-        } else if let Ok(value) = items[2].parse::<i64>() {
-            Self {
-                left: Operand::Column(items[0].to_string()),
-                operator,
-                right: Operand::Number(value),
+        );
+        assert_eq!(
+            parse("#3 BETWEEN -1 AND 2.5e1"),
+            Expression::Between {
+                operand: column("#3"),
+                low: Operand::Number(Int(-1)),
+                high: Operand::Number(Float(25.)),
+                negated: false,
             }
-        } else {
-            return Err(CsvColError::Filter(
-                "Filter should have 2 operands and 1 operator".to_string(),
-            ));
+        );
+        assert_eq!(
+            parse("value is not null"),
+            Expression::IsNull {
+                operand: column("value"),
+                negated: true,
+            }
+        );
+        assert_eq!(
+            parse("name == 'it''s'"),
+            Expression::Compare(
+                column("name"),
+                Operator::Eq,
+                Operand::Text("it's".to_string())
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_error("value > && 3"),
+            "Can't parse filter: expected a column, number or string at character 9\n    value > && 3\n            ^"
+        );
+        assert!(
+            parse_error("value > 10)")
+                .contains("expected '&&', '||' or end of filter at character 11")
+        );
+        assert!(parse_error("(value > 10").contains("expected ')' at character 12"));
+        assert!(parse_error("value ~ 10").contains("unexpected character '~' at character 7"));
+        assert!(parse_error("value > 'paid").contains("unterminated quote at character 9"));
+        assert!(parse_error("value > 12abc").contains("invalid number '12abc' at character 9"));
+        assert!(
+            parse_error("value in (1, 'a')")
+                .contains("can't compare number with string at character 14")
+        );
+        assert!(parse_error("value").contains("expected a comparison, IN, BETWEEN or IS NULL"));
+        assert!(parse_error("in > 1").contains("unexpected keyword in"));
+        assert!(
+            parse_error("a * 'x' > 1").contains("can't do arithmetic on a string at character 3")
+        );
+        assert!(
+            parse_error("a + 1 == 'x'").contains("can't compare number with string at character 7")
+        );
+        assert!(parse_error("-'x' < a").contains("can't negate a string at character 1"));
+        assert!(parse_error("a IN (b)").contains("expected a number or string at character 7"));
+        assert!(
+            parse_error("(a + b) && c > 1")
+                .contains("expected a comparison, IN, BETWEEN or IS NULL at character 9")
+        );
+        assert!(parse_error("((a + b) > 1").contains("expected ')' at character 13"));
+        assert!(
+            parse_error("(a > 1) + 2")
+                .contains("expected '&&', '||' or end of filter at character 9")
+        );
+        assert!(parse_error("").contains("at character 1"));
+    }
+
+    #[test]
+    fn test_parse_arithmetic() {
+        let arithmetic =
+            |left, operator, right| Operand::Arithmetic(Box::new(left), operator, Box::new(right));
+
+        assert_eq!(
+            parse("price > cost * 1.2 + -fee"),
+            Expression::Compare(
+                column("price"),
+                Operator::Gt,
+                arithmetic(
+                    arithmetic(
+                        column("cost"),
+                        ArithmeticOperator::Mul,
+                        Operand::Number(Float(1.2))
+                    ),
+                    ArithmeticOperator::Add,
+                    Operand::Negate(Box::new(column("fee"))),
+                )
+            )
+        );
+        assert_eq!(
+            parse("(a - b) / 2 >= 1"),
+            Expression::Compare(
+                arithmetic(
+                    arithmetic(column("a"), ArithmeticOperator::Sub, column("b")),
+                    ArithmeticOperator::Div,
+                    Operand::Number(Int(2))
+                ),
+                Operator::Ge,
+                Operand::Number(Int(1))
+            )
+        );
+        assert_eq!(parse("((a > b))"), parse("a > b"));
+        assert_eq!(
+            parse("unit-price > 10"),
+            Expression::Compare(column("unit-price"), Operator::Gt, Operand::Number(Int(10)))
+        );
+        assert_eq!(
+            parse("unit - price > 10-2"),
+            Expression::Compare(
+                arithmetic(column("unit"), ArithmeticOperator::Sub, column("price")),
+                Operator::Gt,
+                arithmetic(
+                    Operand::Number(Int(10)),
+                    ArithmeticOperator::Sub,
+                    Operand::Number(Int(2))
+                )
+            )
+        );
+        assert_eq!(parse("a -b > 1"), parse("a - b > 1"));
+        assert_eq!(parse("a- b > 1"), parse("a - b > 1"));
+        assert_eq!(
+            parse("(a + 1 > b) && c IS NULL"),
+            Expression::And(Box::new(parse("a + 1 > b")), Box::new(parse("c IS NULL")))
+        );
+    }
+
+    #[test]
+    fn test_arithmetic_apply() {
+        use ArithmeticOperator::*;
+
+        assert_eq!(Add.apply(Int(2), Int(3)), Some(Int(5)));
+        assert_eq!(
+            Mul.apply(Int(i64::MAX), Int(2)),
+            Some(Float(i64::MAX as f64 * 2.))
+        );
+        assert_eq!(Sub.apply(Float(1.5), Int(1)), Some(Float(0.5)));
+        assert_eq!(Div.apply(Int(3), Int(2)), Some(Float(1.5)));
+        assert_eq!(Div.apply(Int(3), Int(0)), None);
+    }
+
+    #[test]
+    fn test_check_column() {
+        let expression = parse("a > 1 && (b IS NULL || #2 IN (1, 2))");
+        assert!(expression.check_column(0, "a"));
+        assert!(expression.check_column(1, "b"));
+        assert!(expression.check_column(2, "c"));
+        assert!(!expression.check_column(3, "d"));
+    }
+
+    #[test]
+    fn test_matches_value() {
+        let matches =
+            |filter: &str, value: Number| parse(filter).for_column(0, "a").matches_value(&value);
+
+        assert!(matches("a > 1", Int(2)));
+        assert!(!matches("a > 1", Int(1)));
+        assert!(matches("a <= 1.5", Float(1.5)));
+        assert!(matches("!(a > 1)", Int(1)));
+        assert!(matches("a IN (1, 2.5)", Float(2.5)));
+        assert!(matches("a NOT IN (1, 2)", Int(3)));
+        assert!(matches("a BETWEEN 1 AND 3", Int(3)));
+        assert!(!matches("a NOT BETWEEN 1 AND 3", Int(2)));
+        assert!(matches("a IS NOT NULL", Int(2)));
+        assert!(!matches("a == 'x'", Int(2)));
+
+        // other columns are unknown, so they only matter if they decide
+        assert!(!matches("a > 1 && b > 1", Int(2)));
+        assert!(matches("a > 1 || b > 1", Int(2)));
+        assert!(!matches("!(b > 1)", Int(2)));
+    }
+
+    #[test]
+    fn test_matches_row() {
+        let headers = ["id".to_string(), "zip".to_string(), "note".to_string()];
+        let matches = |filter: &str, row: [Value<'static>; 3]| {
+            parse(filter)
+                .resolve(&headers)
+                .unwrap()
+                .matches_row(|index| row[index])
         };
+        let zip = Value {
+            text: Some("01234"),
+            number: Some(Int(1234)),
+            ..Value::NULL
+        };
+        let row = [Value::number(Int(1)), zip, Value::NULL];
 
-        Ok(expression)
+        // fields compare as numbers against numbers and as text against strings
+        assert!(matches("zip == 1234", row));
+        assert!(matches("zip == '01234'", row));
+        assert!(matches("#0 == 1 && note IS NULL", row));
+        assert!(!matches("note == 'x' || note != 'x'", row));
+        assert!(matches(
+            "id < 5",
+            [Value::number(Int(4)), Value::NULL, Value::NULL]
+        ));
+
+        // columns compare per row, arithmetic on non-numbers is null
+        assert!(matches("zip > id * 1000", row));
+        assert!(!matches("zip < id * 1000 || zip > id * 2000", row));
+        assert!(matches("zip / (id - 1) IS NULL", row));
+        assert!(matches("note + 1 IS NULL && -zip == -1234", row));
+
+        // a number and a field that isn't one don't compare, even as text
+        let field = |text| Value {
+            text: Some(text),
+            number: text.parse().ok().map(Int),
+            ..Value::NULL
+        };
+        let row = [field("13"), field("x1"), field("abc")];
+        assert!(!matches("id > zip || id <= zip", row));
+        assert!(!matches("id + 0 > zip || id + 0 <= zip", row));
+        assert!(matches("id == '13' && zip > note", row));
+
+        assert!(matches!(
+            parse("missing > 1").resolve(&headers),
+            Err(CsvColError::UnknownFilterColumn(name)) if name == "missing"
+        ));
     }
 }
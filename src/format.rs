@@ -0,0 +1,405 @@
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+use tabled::{
+    Table,
+    settings::{Alignment, Style, object::Columns},
+};
+
+use crate::{
+    ColumnRecord, FileReport, Format, Report, Rounding, RowCounts, TableView, human_count,
+    human_number, human_opt_number,
+};
+
+/// Renders parsed files in one output format.
+///
+/// [`Formatter::file`] is called for every file as soon as it's parsed, in
+/// completion order, then [`Formatter::finish`] once with the whole report in
+/// input order. Formats pick whichever fits, so streaming formats don't wait
+/// for the slowest file.
+pub trait Formatter {
+    fn file(&mut self, _out: &mut dyn Write, _file: &FileReport) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn finish(&mut self, _out: &mut dyn Write, _report: &Report) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Formatter for `format`.
+///
+/// Statistics are expected to be rounded already, `rounding` only tells the
+/// human format how many digits to print, 4 significant by default.
+pub fn formatter(format: Format, rounding: Option<Rounding>) -> Box<dyn Formatter> {
+    match format {
+        Format::Human => Box::new(HumanFormatter {
+            rounding: rounding.unwrap_or(Rounding::HUMAN),
+        }),
+        Format::Table => Box::new(TableFormatter),
+        Format::Json => Box::new(JsonFormatter),
+        Format::Ndjson => Box::new(NdjsonFormatter),
+        Format::Csv => Box::new(CsvFormatter),
+        Format::Markdown => Box::new(MarkdownFormatter),
+    }
+}
+
+fn table_rows(file: &FileReport) -> Vec<TableView> {
+    file.column_records()
+        .map(|record| TableView::from((record.column, record.stats)))
+        .collect()
+}
+
+/// Row counts line of the table and markdown formats, filtered rows are only
+/// mentioned when a row filter skipped some.
+fn row_summary(counts: &RowCounts) -> String {
+    let mut summary = format!("Rows: {}, ragged rows: {}", counts.rows, counts.ragged_rows);
+    if counts.filtered_rows > 0 {
+        summary += &format!(", filtered rows: {}", counts.filtered_rows);
+    }
+    summary
+}
+
+/// Files that were parsed, failed ones are only reported on stderr.
+fn parsed_files(report: &Report) -> impl Iterator<Item = &FileReport> {
+    report.files.iter().filter(|file| file.output.is_some())
+}
+
+/// Aligned columns per file in header order, with rounded numbers and a
+/// summary line.
+struct HumanFormatter {
+    rounding: Rounding,
+}
+
+const HUMAN_HEADER: [&str; 10] = [
+    "column",
+    "count",
+    "nulls",
+    "non_numeric",
+    "min",
+    "max",
+    "mean",
+    "median",
+    "stddev",
+    "precision_loss",
+];
+
+fn human_duration(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs_f64();
+    if seconds >= 1. {
+        format!("{} s", human_number(seconds, Rounding::HUMAN))
+    } else {
+        format!("{} ms", human_number(seconds * 1e3, Rounding::HUMAN))
+    }
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    let suffix = if count == 1 { "" } else { "s" };
+    format!("{} {noun}{suffix}", human_count(count))
+}
+
+impl Formatter for HumanFormatter {
+    fn finish(&mut self, out: &mut dyn Write, report: &Report) -> io::Result<()> {
+        let rounding = self.rounding;
+        for (index, file) in parsed_files(report).enumerate() {
+            let Some(output) = &file.output else {
+                continue;
+            };
+            if index > 0 {
+                writeln!(out)?;
+            }
+            writeln!(out, "File: {}", file.path)?;
+
+            let mut rows: Vec<Vec<String>> =
+                vec![HUMAN_HEADER.iter().map(|name| name.to_string()).collect()];
+            if let Some((_, stats)) = output.columns.iter().next() {
+                rows[0].extend(stats.quantiles.iter().map(|q| q.label()));
+            }
+            for (column, stats) in output.columns.iter() {
+                let mut row = vec![
+                    column.to_string(),
+                    human_count(stats.count),
+                    human_count(stats.null_count),
+                    human_count(stats.non_numeric_count),
+                    human_opt_number(stats.min.map(|v| v.as_f64()), rounding),
+                    human_opt_number(stats.max.map(|v| v.as_f64()), rounding),
+                    human_opt_number(stats.mean, rounding),
+                    human_opt_number(stats.median, rounding),
+                    human_opt_number(stats.stddev, rounding),
+                    if stats.precision_loss { "yes" } else { "no" }.to_string(),
+                ];
+                row.extend(
+                    stats
+                        .quantiles
+                        .iter()
+                        .map(|q| human_opt_number(q.value, rounding)),
+                );
+                rows.push(row);
+            }
+
+            let mut widths = vec![0; rows[0].len()];
+            for row in &rows {
+                for (width, cell) in widths.iter_mut().zip(row) {
+                    *width = (*width).max(cell.chars().count());
+                }
+            }
+            for row in &rows {
+                // names are left aligned, numbers right aligned
+                let cells: Vec<String> = row
+                    .iter()
+                    .zip(&widths)
+                    .enumerate()
+                    .map(|(index, (cell, &width))| match index {
+                        0 => format!("{cell:<width$}"),
+                        _ => format!("{cell:>width$}"),
+                    })
+                    .collect();
+                writeln!(out, "  {}", cells.join("  ").trim_end())?;
+            }
+
+            let counts = output.row_counts;
+            let filtered = match counts.filtered_rows {
+                0 => String::new(),
+                rows => format!(", {} filtered", human_count(rows)),
+            };
+            writeln!(
+                out,
+                "{} ({} ragged{filtered}), {} in {}",
+                plural(counts.rows, "row"),
+                human_count(counts.ragged_rows),
+                plural(output.columns.len(), "column"),
+                human_duration(file.elapsed),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Table per file, columns sorted alphabetically.
+struct TableFormatter;
+
+impl Formatter for TableFormatter {
+    fn finish(&mut self, out: &mut dyn Write, report: &Report) -> io::Result<()> {
+        for file in parsed_files(report) {
+            let mut rows = table_rows(file);
+            rows.sort_by(|a, b| a.column_name.cmp(&b.column_name));
+            let mut table = Table::new(rows);
+            table.with(Style::modern());
+            table.modify(Columns::first(), Alignment::right());
+
+            writeln!(out, "File: {}", file.path)?;
+            writeln!(out, "{table}")?;
+            if let Some(output) = &file.output {
+                writeln!(out, "{}", row_summary(&output.row_counts))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Single document covering all files.
+struct JsonFormatter;
+
+impl Formatter for JsonFormatter {
+    fn finish(&mut self, out: &mut dyn Write, report: &Report) -> io::Result<()> {
+        serde_json::to_writer(&mut *out, report)?;
+        writeln!(out)
+    }
+}
+
+/// One line per file and column, written as soon as the file is parsed.
+struct NdjsonFormatter;
+
+impl Formatter for NdjsonFormatter {
+    fn file(&mut self, out: &mut dyn Write, file: &FileReport) -> io::Result<()> {
+        for record in file.column_records() {
+            serde_json::to_writer(&mut *out, &record)?;
+            writeln!(out)?;
+        }
+        out.flush()
+    }
+}
+
+/// One row per file and column, with a column per requested quantile.
+struct CsvFormatter;
+
+const CSV_HEADER: [&str; 16] = [
+    "file",
+    "column",
+    "count",
+    "null_count",
+    "non_numeric_count",
+    "min",
+    "max",
+    "mean",
+    "median",
+    "variance",
+    "sample_variance",
+    "stddev",
+    "sample_stddev",
+    "skewness",
+    "kurtosis",
+    "precision_loss",
+];
+
+fn csv_row(record: &ColumnRecord) -> Vec<String> {
+    let stats = record.stats;
+    let optional = |value: &Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+    let mut row = vec![
+        record.file.to_string(),
+        record.column.to_string(),
+        stats.count.to_string(),
+        stats.null_count.to_string(),
+        stats.non_numeric_count.to_string(),
+        stats.min.map(|v| v.to_string()).unwrap_or_default(),
+        stats.max.map(|v| v.to_string()).unwrap_or_default(),
+        optional(&stats.mean),
+        optional(&stats.median),
+        optional(&stats.variance),
+        optional(&stats.sample_variance),
+        optional(&stats.stddev),
+        optional(&stats.sample_stddev),
+        optional(&stats.skewness),
+        optional(&stats.kurtosis),
+        stats.precision_loss.to_string(),
+    ];
+    row.extend(stats.quantiles.iter().map(|q| optional(&q.value)));
+    row
+}
+
+impl Formatter for CsvFormatter {
+    fn finish(&mut self, out: &mut dyn Write, report: &Report) -> io::Result<()> {
+        let mut writer = csv::Writer::from_writer(out);
+        let mut records = parsed_files(report).flat_map(FileReport::column_records);
+
+        // quantiles are the same for every column, the first one names them
+        let Some(first) = records.next() else {
+            return Ok(());
+        };
+        let mut header: Vec<String> = CSV_HEADER.iter().map(|name| name.to_string()).collect();
+        header.extend(first.stats.quantiles.iter().map(|q| q.label()));
+        writer.write_record(&header)?;
+
+        for record in std::iter::once(first).chain(records) {
+            writer.write_record(csv_row(&record))?;
+        }
+        writer.flush()
+    }
+}
+
+/// Section per file with a table in header order, ready to paste in
+/// Markdown documents.
+struct MarkdownFormatter;
+
+impl Formatter for MarkdownFormatter {
+    fn finish(&mut self, out: &mut dyn Write, report: &Report) -> io::Result<()> {
+        for (index, file) in parsed_files(report).enumerate() {
+            if index > 0 {
+                writeln!(out)?;
+            }
+            writeln!(out, "### {}", file.path.replace('|', "\\|"))?;
+            writeln!(out)?;
+            if let Some(output) = &file.output {
+                writeln!(out, "{}", row_summary(&output.row_counts))?;
+                writeln!(out)?;
+            }
+            let mut rows = table_rows(file);
+            for row in &mut rows {
+                row.column_name = row.column_name.replace('|', "\\|");
+            }
+            let mut table = Table::new(rows);
+            table.with(Style::markdown());
+            writeln!(out, "{table}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Number::Int, Output, Quantile, Stats};
+
+    use super::*;
+
+    fn report() -> Report {
+        let stats = Stats {
+            count: 2,
+            min: Some(Int(1)),
+            max: Some(Int(3)),
+            mean: Some(2.),
+            quantiles: vec![Quantile {
+                quantile: 0.5,
+                value: Some(2.),
+            }],
+            ..Stats::default()
+        };
+        let output = Output {
+            columns: [("a|b".to_string(), stats)].into_iter().collect(),
+            ..Output::default()
+        };
+        Report::new(vec![
+            FileReport::new("data.csv".to_string(), Ok(output)),
+            FileReport::new("bad.csv".to_string(), Err(crate::CsvColError::ThreadPanic)),
+        ])
+    }
+
+    fn render(format: Format) -> String {
+        let report = report();
+        let mut formatter = formatter(format, None);
+        let mut out = Vec::new();
+        for file in &report.files {
+            formatter.file(&mut out, file).unwrap();
+        }
+        formatter.finish(&mut out, &report).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_csv_format() {
+        assert_eq!(
+            render(Format::Csv),
+            "file,column,count,null_count,non_numeric_count,min,max,mean,median,variance,\
+             sample_variance,stddev,sample_stddev,skewness,kurtosis,precision_loss,p50\n\
+             data.csv,a|b,2,0,0,1,3,2,,,,,,,,false,2\n"
+        );
+    }
+
+    #[test]
+    fn test_markdown_format() {
+        let markdown = render(Format::Markdown);
+        let lines: Vec<_> = markdown.lines().collect();
+        assert_eq!(lines[0], "### data.csv");
+        assert_eq!(lines[2], "Rows: 0, ragged rows: 0");
+        assert!(lines[4].starts_with("| column_name | count |"));
+        assert!(lines[5].starts_with("|-"));
+        assert!(lines[6].starts_with("| a\\|b "), "{}", lines[6]);
+        assert_eq!(lines.len(), 7);
+    }
+
+    #[test]
+    fn test_human_format() {
+        let mut report = report();
+        report.files[0].elapsed = Duration::from_micros(1500);
+        let mut out = Vec::new();
+        formatter(Format::Human, None)
+            .finish(&mut out, &report)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "File: data.csv\n  \
+             column  count  nulls  non_numeric  min  max  mean  median  stddev  precision_loss  p50\n  \
+             a|b         2      0            0    1    3     2       -       -              no    2\n\
+             0 rows (0 ragged), 1 column in 1.5 ms\n"
+        );
+    }
+
+    #[test]
+    fn test_ndjson_format() {
+        let ndjson = render(Format::Ndjson);
+        assert_eq!(ndjson.lines().count(), 1);
+        assert!(ndjson.starts_with("{\"file\":\"data.csv\",\"column\":\"a|b\",\"count\":2,"));
+    }
+}
@@ -1,19 +1,33 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    cmp::Ordering,
+    collections::HashSet,
+    fmt,
+    path::{Path, PathBuf},
+};
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use serde::Serialize;
 
 mod error;
 mod filter;
+mod format;
 mod parser;
+mod report;
 
 pub use error::{CsvColError, Result};
-pub use parser::parse_file;
+pub use format::{Formatter, formatter};
+pub use parser::{RejectWriter, parse_file, parse_stream};
+pub use report::{ColumnRecord, FileReport, Report, SCHEMA_VERSION};
 use tabled::Tabled;
 
 use crate::filter::Expression;
 
 const DEFAULT_MEMORY_BUDGET: usize = 256 * 1024 * 1024;
+const DEFAULT_NULL_VALUES: [&str; 4] = ["NaN", "nan", "null", "N/A"];
+const DEFAULT_SNIFF_SIZE: usize = 64 * 1024;
+/// Most decimal digits an `f64` can carry, more rounding digits only print
+/// noise.
+const MAX_DIGITS: i64 = 17;
 
 /// Command-line arguments for the CSV column statistics parser.
 ///
@@ -26,35 +40,168 @@ pub struct CsvColStatsArgs {
     #[arg(short, long)]
     pub table: bool,
 
-    /// Emit output as JSON.
+    /// Emit output as a single JSON document covering all files.
     ///
     /// This option overrides `--table` and is intended for machine consumption
-    /// or post-processing with tools such as `jq`.
+    /// or post-processing with tools such as `jq`. The document carries a
+    /// `schema_version` and lists files, then columns, in input order.
     #[arg(short, long)]
     pub json: bool,
 
-    /// List of column names to ignore during parsing.
+    /// Round printed statistics to N decimal places.
+    ///
+    /// Applies to every format, JSON and CSV keep full precision unless this
+    /// or `--significant-digits` is given.
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with = "significant_digits",
+        value_parser = clap::value_parser!(u32).range(0..=MAX_DIGITS)
+    )]
+    pub precision: Option<u32>,
+
+    /// Round printed statistics to N significant digits.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..=MAX_DIGITS))]
+    pub significant_digits: Option<u32>,
+
+    /// Output format, overrides `--table` and `--json`.
+    ///
+    /// `human`, the default, prints an aligned summary per file with rounded
+    /// numbers.
+    /// `ndjson` prints one JSON object per file and column as soon as each
+    /// file is parsed, `csv` one row per file and column and `markdown` a
+    /// table per file.
+    #[arg(long, value_enum)]
+    pub format: Option<Format>,
+
+    /// List of columns to ignore during parsing, by name or by zero-based
+    /// index like `#3`.
     ///
     /// By default, the `id` column is ignored.
     #[arg(short, long, default_value = "id")]
     pub ignore_columns: Vec<String>,
 
-    /// Optional filter expression applied to column values.
+    /// Comma separated tokens treated as null, in addition to empty cells.
+    ///
+    /// Default: `NaN,nan,null,N/A`
+    #[arg(long, value_delimiter = ',', default_values = DEFAULT_NULL_VALUES)]
+    pub null_values: Vec<String>,
+
+    /// Match `--null-values` ignoring ASCII case.
+    #[arg(long)]
+    pub null_values_ignore_case: bool,
+
+    /// What to do with a cell that can't be parsed in a numeric column.
+    #[arg(long, value_enum, default_value_t)]
+    pub on_error: OnError,
+
+    /// Maximum number of invalid cells recorded per column when
+    /// `--on-error` is `skip` or `null`.
+    #[arg(long, default_value_t = 0)]
+    pub error_samples: usize,
+
+    /// Detect the dialect, header presence and extra null tokens from the
+    /// start of each input.
+    ///
+    /// Dialect flags given explicitly take precedence over detected values.
+    #[arg(long)]
+    pub sniff: bool,
+
+    /// Number of bytes sampled by `--sniff`.
+    ///
+    /// Default: 64KB
+    #[arg(long, default_value_t = DEFAULT_SNIFF_SIZE, value_name = "BYTES")]
+    pub sniff_size: usize,
+
+    /// Field delimiter, a single ASCII character or `\t`.
+    ///
+    /// Default: `,`
+    #[arg(long, value_parser = parse_byte)]
+    pub delimiter: Option<u8>,
+
+    /// Quote character.
+    ///
+    /// Default: `"`
+    #[arg(long, value_parser = parse_byte)]
+    pub quote: Option<u8>,
+
+    /// Escape character for quotes inside quoted fields, e.g. `\`.
+    ///
+    /// By default quotes are escaped by doubling them.
+    #[arg(long, value_parser = parse_byte)]
+    pub escape: Option<u8>,
+
+    /// Lines starting with this character are skipped, e.g. `#`.
+    #[arg(long, value_parser = parse_byte)]
+    pub comment: Option<u8>,
+
+    /// Don't treat two consecutive quotes in a quoted field as an escaped
+    /// quote.
+    #[arg(long)]
+    pub no_double_quote: bool,
+
+    /// The first record is data rather than column names.
+    ///
+    /// Columns are named `col_0..col_n` unless `--names` is given.
+    #[arg(long)]
+    pub no_header: bool,
+
+    /// Comma separated column names, replacing the header row or the
+    /// positional names of `--no-header`.
+    #[arg(long, value_delimiter = ',')]
+    pub names: Vec<String>,
+
+    /// Record terminator, `crlf` (any of `\r`, `\n` or `\r\n`) or a single
+    /// ASCII character.
+    ///
+    /// Default: `crlf`
+    #[arg(long, value_parser = parse_terminator)]
+    pub terminator: Option<Terminator>,
+
+    /// How to handle rows with a different number of fields than the header.
+    #[arg(long, value_enum, default_value_t)]
+    pub ragged: RaggedRows,
+
+    /// Write every row that failed parsing to this CSV file, as it appeared
+    /// in the input, together with its source file, line number and the
+    /// reason it was rejected.
+    #[arg(long, value_name = "PATH")]
+    pub reject_file: Option<PathBuf>,
+
+    /// Optional filter expression selecting the rows, or with
+    /// `--filter-mode column` the column values, that are aggregated.
     ///
-    /// This accept simple expression like "value > 10"
+    /// Conditions like "value > 10", "value IN (1, 2)", "value BETWEEN 1
+    /// AND 5" or "value IS NULL" are combined with "&&", "||", "!" and
+    /// parentheses. In row mode operands can be other columns and
+    /// arithmetic on them like "price > cost * 1.2". Columns can also be referenced by
+    /// zero-based index like "#3 > 10" or quoted with backticks like
+    /// "`my col` > 10". A "-" without spaces around it is part of a column
+    /// name like "unit-price", subtract with "a - b"
     #[arg(short, long)]
     pub filter: Option<Expression>,
 
+    /// What `--filter` applies to.
+    #[arg(long, value_enum, default_value_t, requires = "filter")]
+    pub filter_mode: FilterMode,
+
     /// Memory budget (in bytes) used to decide between exact and approximate
     /// median calculation.
     ///
     /// Default: 256MB
     ///
-    /// If the input file size exceeds this budget, an approximate median
-    /// algorithm is used.
+    /// If the input file size exceeds this budget, the median is calculated
+    /// using the `--median-fallback` strategy.
     #[arg(long, default_value_t=DEFAULT_MEMORY_BUDGET)]
     pub memory_budget: usize,
 
+    /// Median strategy used when a file exceeds the memory budget.
+    ///
+    /// `approximate` keeps a bounded sketch in memory, `spill` stays exact by
+    /// writing sorted runs of values to temp files.
+    #[arg(long, value_enum, default_value_t)]
+    pub median_fallback: MedianFallback,
+
     /// Optional number of bins to use for approximate median calculation.
     ///
     /// When specified, this value overrides the default bin configuration
@@ -62,16 +209,150 @@ pub struct CsvColStatsArgs {
     #[arg(long)]
     pub approximate_bins: Option<u32>,
 
-    /// One or more CSV files to process.
+    /// Comma separated quantiles to calculate for every column, e.g.
+    /// `0.01,0.25,0.75,0.99`.
+    ///
+    /// Each value must be within `[0, 1]`. Quantiles are exact or approximate
+    /// following the same rule as the median.
+    #[arg(long, value_delimiter = ',', value_parser = parse_quantile)]
+    pub quantiles: Vec<f64>,
+
+    /// CSV files to process, `-` or no files reads from standard input.
     #[arg(value_name = "FILE", num_args = 1..)]
     pub files: Vec<PathBuf>,
 }
 
+impl CsvColStatsArgs {
+    /// Rounding selected by `--precision` or `--significant-digits`.
+    pub fn rounding(&self) -> Option<Rounding> {
+        self.precision
+            .map(Rounding::Decimals)
+            .or(self.significant_digits.map(Rounding::Significant))
+    }
+
+    /// Format selected by `--format`, `--json` or `--table`, in that order.
+    pub fn output_format(&self) -> Format {
+        self.format
+            .or(self.json.then_some(Format::Json))
+            .or(self.table.then_some(Format::Table))
+            .unwrap_or_default()
+    }
+}
+
+/// Format of the printed statistics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Aligned summary per file with human friendly numbers.
+    #[default]
+    Human,
+    /// Human-readable table per file.
+    Table,
+    /// Single JSON document covering all files.
+    Json,
+    /// One JSON object per line for every file and column.
+    Ndjson,
+    /// One row per file and column.
+    Csv,
+    /// Markdown table per file.
+    Markdown,
+}
+
+/// Rounding of printed statistics, applied when formatting the output so
+/// accumulators keep full precision.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// Number of digits after the decimal point.
+    Decimals(u32),
+    /// Number of significant digits, at least one.
+    Significant(u32),
+}
+
+impl Rounding {
+    /// Default of the human format.
+    pub const HUMAN: Self = Self::Significant(4);
+
+    /// Number of decimal places to keep for `value`, negative to round to
+    /// tens, hundreds and so on.
+    fn decimals(&self, value: f64) -> i32 {
+        match *self {
+            Rounding::Decimals(decimals) => decimals as i32,
+            Rounding::Significant(_) if value == 0. => 0,
+            Rounding::Significant(digits) => {
+                digits.max(1) as i32 - 1 - value.abs().log10().floor() as i32
+            }
+        }
+    }
+
+    pub fn apply(&self, value: f64) -> f64 {
+        if !value.is_finite() {
+            return value;
+        }
+        let rounded = match self.decimals(value) {
+            // decimal formatting rounds exactly, unlike scaling by powers of ten
+            decimals @ 0.. => format!("{value:.0$}", decimals as usize)
+                .parse()
+                .unwrap_or(value),
+            decimals => {
+                let scale = 10f64.powi(-decimals);
+                (value / scale).round() * scale
+            }
+        };
+        // turns `-0` from rounding small negative values into `0`
+        rounded + 0.
+    }
+}
+
+fn parse_quantile(value: &str) -> std::result::Result<f64, String> {
+    let quantile: f64 = value
+        .trim()
+        .parse()
+        .map_err(|_| format!("'{value}' is not a number"))?;
+    if !(0.0..=1.0).contains(&quantile) {
+        return Err(format!("quantile {quantile} is outside of [0, 1]"));
+    }
+    Ok(quantile)
+}
+
+/// How medians and quantiles are calculated once a file doesn't fit in the
+/// memory budget.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum MedianFallback {
+    /// Approximate with DDSketch.
+    #[default]
+    Approximate,
+    /// Exact, spilling sorted runs to temp files and merging them.
+    Spill,
+}
+
+fn parse_byte(value: &str) -> std::result::Result<u8, String> {
+    match value {
+        "\\t" | "tab" => Ok(b'\t'),
+        _ => match value.as_bytes() {
+            [byte] if byte.is_ascii() => Ok(*byte),
+            _ => Err(format!("'{value}' is not a single ASCII character")),
+        },
+    }
+}
+
+fn parse_terminator(value: &str) -> std::result::Result<Terminator, String> {
+    match value {
+        "crlf" | "CRLF" => Ok(Terminator::Crlf),
+        "\\n" => Ok(Terminator::Byte(b'\n')),
+        "\\r" => Ok(Terminator::Byte(b'\r')),
+        _ => parse_byte(value).map(Terminator::Byte),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct MedianConfig {
     pub memory_budget: usize,
     pub buckets: Option<u32>,
     pub exact_median: bool,
+    pub fallback: MedianFallback,
+    /// Input size is unknown, start exact and switch to `fallback` once a
+    /// column outgrows the memory budget.
+    pub streaming: bool,
+    pub quantiles: Vec<f64>,
 }
 
 impl Default for MedianConfig {
@@ -80,14 +361,205 @@ impl Default for MedianConfig {
             memory_budget: DEFAULT_MEMORY_BUDGET,
             buckets: None,
             exact_median: true,
+            fallback: MedianFallback::default(),
+            streaming: false,
+            quantiles: Vec::new(),
+        }
+    }
+}
+
+/// Policy for cells that can't be parsed in a numeric column.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OnError {
+    /// Abort parsing the file.
+    #[default]
+    Fail,
+    /// Skip the cell and count it as non-numeric. A CSV stream that can't
+    /// be read any further keeps the rows read so far, with a warning.
+    Skip,
+    /// Treat the cell as null.
+    Null,
+}
+
+/// Record terminator of a CSV dialect.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Terminator {
+    /// Any of `\r`, `\n` or `\r\n`.
+    #[default]
+    Crlf,
+    Byte(u8),
+}
+
+impl Serialize for Terminator {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Terminator::Crlf => serializer.serialize_str("crlf"),
+            Terminator::Byte(byte) => serializer.serialize_char(char::from(*byte)),
+        }
+    }
+}
+
+/// CSV dialect used to read the input.
+///
+/// Serialized with characters as strings, e.g. `"delimiter": ";"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Dialect {
+    #[serde(serialize_with = "serialize_byte")]
+    pub delimiter: u8,
+    #[serde(serialize_with = "serialize_byte")]
+    pub quote: u8,
+    #[serde(serialize_with = "serialize_opt_byte")]
+    pub escape: Option<u8>,
+    #[serde(serialize_with = "serialize_opt_byte")]
+    pub comment: Option<u8>,
+    pub double_quote: bool,
+    pub terminator: Terminator,
+    /// The first record holds column names.
+    pub has_header: bool,
+}
+
+impl Default for Dialect {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            quote: b'"',
+            escape: None,
+            comment: None,
+            double_quote: true,
+            terminator: Terminator::Crlf,
+            has_header: true,
+        }
+    }
+}
+
+fn serialize_byte<S: serde::Serializer>(
+    byte: &u8,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.serialize_char(char::from(*byte))
+}
+
+fn serialize_opt_byte<S: serde::Serializer>(
+    byte: &Option<u8>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    match byte {
+        Some(byte) => serialize_byte(byte, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Dialect settings given explicitly, taking precedence over sniffed ones.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DialectOverrides {
+    pub delimiter: Option<u8>,
+    pub quote: Option<u8>,
+    pub escape: Option<u8>,
+    pub comment: Option<u8>,
+    pub double_quote: Option<bool>,
+    pub terminator: Option<Terminator>,
+    pub has_header: Option<bool>,
+}
+
+impl DialectOverrides {
+    /// Replaces the fields of `dialect` that were given explicitly.
+    pub fn apply(&self, dialect: Dialect) -> Dialect {
+        Dialect {
+            delimiter: self.delimiter.unwrap_or(dialect.delimiter),
+            quote: self.quote.unwrap_or(dialect.quote),
+            escape: self.escape.or(dialect.escape),
+            comment: self.comment.or(dialect.comment),
+            double_quote: self.double_quote.unwrap_or(dialect.double_quote),
+            terminator: self.terminator.unwrap_or(dialect.terminator),
+            has_header: self.has_header.unwrap_or(dialect.has_header),
+        }
+    }
+}
+
+/// Settings of the dialect sniffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SniffConfig {
+    /// Number of bytes sampled from the start of the input.
+    pub sample_size: usize,
+    pub overrides: DialectOverrides,
+}
+
+/// Policy for rows with a different number of fields than the header.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum RaggedRows {
+    /// Abort parsing the file.
+    #[default]
+    Error,
+    /// Count missing fields as nulls and drop extra fields.
+    Pad,
+    /// Use the fields that are present and drop extra fields.
+    Truncate,
+    /// Skip the whole row.
+    Skip,
+}
+
+/// Scope of a filter expression.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum FilterMode {
+    /// Skip rows not matching the filter in every column.
+    #[default]
+    Row,
+    /// Skip values not matching the filter only in the columns it
+    /// references, other columns aggregate every row.
+    Column,
+}
+
+/// Set of tokens marking a missing value.
+#[derive(Debug, Clone)]
+pub struct NullValues {
+    tokens: HashSet<Vec<u8>>,
+    ignore_case: bool,
+}
+
+impl NullValues {
+    pub fn new<T: AsRef<[u8]>>(tokens: impl IntoIterator<Item = T>, ignore_case: bool) -> Self {
+        Self {
+            tokens: tokens.into_iter().map(|t| t.as_ref().to_vec()).collect(),
+            ignore_case,
         }
     }
+
+    pub fn insert(&mut self, token: impl AsRef<[u8]>) {
+        self.tokens.insert(token.as_ref().to_vec());
+    }
+
+    pub fn contains(&self, bytes: &[u8]) -> bool {
+        if self.ignore_case {
+            self.tokens.iter().any(|t| t.eq_ignore_ascii_case(bytes))
+        } else {
+            self.tokens.contains(bytes)
+        }
+    }
+}
+
+impl Default for NullValues {
+    fn default() -> Self {
+        Self::new(DEFAULT_NULL_VALUES, false)
+    }
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct DataConfig {
+    dialect: Dialect,
+    sniff: Option<SniffConfig>,
     filter: Option<Expression>,
+    filter_mode: FilterMode,
     ignore_columns: Vec<String>,
+    /// Column names replacing the header row.
+    names: Vec<String>,
+    null_values: NullValues,
+    on_error: OnError,
+    error_samples: usize,
+    ragged: RaggedRows,
+    reject_writer: Option<RejectWriter>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -96,57 +568,477 @@ pub struct Config {
     pub median_config: MedianConfig,
 }
 
+impl Config {
+    /// Creates the file collecting rejected rows, shared by all clones of
+    /// this configuration.
+    pub fn open_reject_file(&mut self, path: &Path) -> Result<()> {
+        self.data_config.reject_writer = Some(RejectWriter::create(path)?);
+        Ok(())
+    }
+}
+
 impl From<&CsvColStatsArgs> for Config {
     fn from(args: &CsvColStatsArgs) -> Self {
+        let overrides = DialectOverrides {
+            delimiter: args.delimiter,
+            quote: args.quote,
+            escape: args.escape,
+            comment: args.comment,
+            double_quote: args.no_double_quote.then_some(false),
+            terminator: args.terminator,
+            has_header: args.no_header.then_some(false),
+        };
         Self {
             data_config: DataConfig {
+                dialect: overrides.apply(Dialect::default()),
+                sniff: args.sniff.then_some(SniffConfig {
+                    sample_size: args.sniff_size,
+                    overrides,
+                }),
                 filter: args.filter.clone(),
+                filter_mode: args.filter_mode,
                 ignore_columns: args.ignore_columns.clone(),
+                names: args.names.clone(),
+                null_values: NullValues::new(&args.null_values, args.null_values_ignore_case),
+                on_error: args.on_error,
+                error_samples: args.error_samples,
+                ragged: args.ragged,
+                reject_writer: None,
             },
             median_config: MedianConfig {
                 memory_budget: args.memory_budget,
                 buckets: args.approximate_bins,
                 exact_median: true,
+                fallback: args.median_fallback,
+                streaming: false,
+                quantiles: args.quantiles.clone(),
             },
         }
     }
 }
 
-// TODO: implement Display for Stats
+/// Numeric value of a column.
+///
+/// Columns start as integers and are promoted to floating point as soon as
+/// the first decimal value is seen.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Number {
+    Int(i64),
+    Float(f64),
+}
+
+impl Number {
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            Number::Int(value) => *value as f64,
+            Number::Float(value) => *value,
+        }
+    }
+}
+
+impl PartialOrd for Number {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Number::Int(left), Number::Int(right)) => Some(left.cmp(right)),
+            (left, right) => left.as_f64().partial_cmp(&right.as_f64()),
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Number::Int(value) => write!(f, "{value}"),
+            Number::Float(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+/// Value of a single requested quantile.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Quantile {
+    pub quantile: f64,
+    pub value: Option<f64>,
+}
+
+impl Quantile {
+    /// Percentile label, e.g. `p99` for `0.99`.
+    pub fn label(&self) -> String {
+        let percent = (self.quantile * 100. * 1e6).round() / 1e6;
+        format!("p{percent}")
+    }
+}
+
+/// Cell rejected while parsing a numeric column.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InvalidValue {
+    /// Zero-based data row index, header excluded.
+    pub row: usize,
+    pub column: String,
+    pub value: String,
+}
+
 #[derive(Debug, Default, PartialEq, Serialize)]
 pub struct Stats {
-    pub min: Option<i64>,
-    pub max: Option<i64>,
+    /// Number of values aggregated into the statistics.
+    pub count: usize,
+    /// Number of empty cells or cells matching one of the null tokens.
+    pub null_count: usize,
+    /// Number of cells that couldn't be parsed as a number.
+    pub non_numeric_count: usize,
+    pub min: Option<Number>,
+    pub max: Option<Number>,
     pub mean: Option<f64>,
     pub median: Option<f64>,
+    /// Population variance.
+    pub variance: Option<f64>,
+    /// Sample variance (Bessel's correction), needs at least two values.
+    pub sample_variance: Option<f64>,
+    /// Population standard deviation.
+    pub stddev: Option<f64>,
+    /// Sample standard deviation, needs at least two values.
+    pub sample_stddev: Option<f64>,
+    /// Population skewness, undefined for constant columns.
+    pub skewness: Option<f64>,
+    /// Population excess kurtosis, undefined for constant columns.
+    pub kurtosis: Option<f64>,
+    /// Requested quantiles in the order given on the command line.
+    pub quantiles: Vec<Quantile>,
+    /// First invalid cells of the column, up to `--error-samples`.
+    pub invalid_samples: Vec<InvalidValue>,
+    /// Set when the running sum overflowed and the mean was calculated
+    /// with reduced precision.
+    pub precision_loss: bool,
 }
 
-pub type Output = HashMap<String, Stats>;
+impl Stats {
+    /// Rounds every floating point statistic, integer extrema are kept.
+    pub fn round(&mut self, rounding: Rounding) {
+        for value in [&mut self.min, &mut self.max].into_iter().flatten() {
+            if let Number::Float(float) = value {
+                *float = rounding.apply(*float);
+            }
+        }
+        for value in [
+            &mut self.mean,
+            &mut self.median,
+            &mut self.variance,
+            &mut self.sample_variance,
+            &mut self.stddev,
+            &mut self.sample_stddev,
+            &mut self.skewness,
+            &mut self.kurtosis,
+        ]
+        .into_iter()
+        .chain(self.quantiles.iter_mut().map(|q| &mut q.value))
+        .flatten()
+        {
+            *value = rounding.apply(*value);
+        }
+    }
+}
+
+/// Single line summary with human friendly numbers, e.g.
+/// `count=3 nulls=0 non_numeric=0 min=1 max=3 mean=2 median=2 stddev=0.8165`.
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "count={} nulls={} non_numeric={} min={} max={} mean={} median={} stddev={}",
+            human_count(self.count),
+            human_count(self.null_count),
+            human_count(self.non_numeric_count),
+            human_opt_number(self.min.map(|v| v.as_f64()), Rounding::HUMAN),
+            human_opt_number(self.max.map(|v| v.as_f64()), Rounding::HUMAN),
+            human_opt_number(self.mean, Rounding::HUMAN),
+            human_opt_number(self.median, Rounding::HUMAN),
+            human_opt_number(self.stddev, Rounding::HUMAN),
+        )?;
+        for quantile in &self.quantiles {
+            write!(
+                f,
+                " {}={}",
+                quantile.label(),
+                human_opt_number(quantile.value, Rounding::HUMAN)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Row level counts of a parsed file.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize)]
+pub struct RowCounts {
+    /// Data rows read, header excluded.
+    pub rows: usize,
+    /// Rows whose number of fields didn't match the header.
+    pub ragged_rows: usize,
+    /// Rows skipped by a row filter.
+    pub filtered_rows: usize,
+}
+
+/// Column statistics in header order.
+///
+/// Serialized as an array of [`Stats`] objects with an extra `name` field,
+/// so the order survives any JSON parser.
+#[derive(Debug, Default, PartialEq)]
+pub struct Columns(Vec<(String, Stats)>);
+
+impl Columns {
+    pub fn get(&self, name: &str) -> Option<&Stats> {
+        self.0
+            .iter()
+            .find(|(column, _)| column == name)
+            .map(|(_, stats)| stats)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Stats> {
+        let index = self.0.iter().position(|(column, _)| column == name)?;
+        Some(self.0.remove(index).1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Stats)> {
+        self.0
+            .iter()
+            .map(|(column, stats)| (column.as_str(), stats))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&str, &mut Stats)> {
+        self.0
+            .iter_mut()
+            .map(|(column, stats)| (column.as_str(), stats))
+    }
+}
+
+impl FromIterator<(String, Stats)> for Columns {
+    fn from_iter<T: IntoIterator<Item = (String, Stats)>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for Columns {
+    type Item = (String, Stats);
+    type IntoIter = std::vec::IntoIter<(String, Stats)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[derive(Serialize)]
+struct NamedStats<'a> {
+    name: &'a str,
+    #[serde(flatten)]
+    stats: &'a Stats,
+}
+
+impl Serialize for Columns {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter().map(|(name, stats)| NamedStats { name, stats }))
+    }
+}
+
+/// Statistics of a single file.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Output {
+    /// Dialect the input was read with, sniffed or configured.
+    pub dialect: Dialect,
+    /// Null tokens found by the sniffer, in addition to the configured ones.
+    pub detected_null_values: Vec<String>,
+    /// Problems that didn't stop parsing, e.g. renamed duplicate headers.
+    pub warnings: Vec<String>,
+    #[serde(flatten)]
+    pub row_counts: RowCounts,
+    pub columns: Columns,
+}
 
 #[derive(Tabled)]
 pub struct TableView {
     pub column_name: String,
+    count: usize,
+    nulls: usize,
+    non_numeric: usize,
     min: String,
     max: String,
     mean: String,
     median: String,
+    variance: String,
+    sample_variance: String,
+    stddev: String,
+    sample_stddev: String,
+    skewness: String,
+    kurtosis: String,
+    quantiles: String,
 }
 
-impl From<(String, Stats)> for TableView {
-    fn from((column_name, stats): (String, Stats)) -> Self {
+impl From<(&str, &Stats)> for TableView {
+    fn from((column_name, stats): (&str, &Stats)) -> Self {
         Self {
-            column_name,
+            column_name: column_name.to_string(),
+            count: stats.count,
+            nulls: stats.null_count,
+            non_numeric: stats.non_numeric_count,
             min: display_opt_num(&stats.min),
             max: display_opt_num(&stats.max),
             mean: display_opt_num(&stats.mean),
             median: display_opt_num(&stats.median),
+            variance: display_opt_num(&stats.variance),
+            sample_variance: display_opt_num(&stats.sample_variance),
+            stddev: display_opt_num(&stats.stddev),
+            sample_stddev: display_opt_num(&stats.sample_stddev),
+            skewness: display_opt_num(&stats.skewness),
+            kurtosis: display_opt_num(&stats.kurtosis),
+            quantiles: stats
+                .quantiles
+                .iter()
+                .map(|q| format!("{}={}", q.label(), display_opt_num(&q.value)))
+                .collect::<Vec<_>>()
+                .join(", "),
         }
     }
 }
 
+/// Count with thousands separators, e.g. `1,234,567`.
+pub(crate) fn human_count(count: usize) -> String {
+    let digits = count.to_string();
+    let mut result = String::with_capacity(digits.len() + digits.len() / 3);
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            result.push(',');
+        }
+        result.push(digit);
+    }
+    result
+}
+
+/// Number rounded to significant digits, with a `k`, `M`, `G` or `T` suffix
+/// from a thousand up and in scientific notation when very large or small.
+///
+/// [`Rounding::Decimals`] prints the plain value with that many decimals
+/// instead.
+pub(crate) fn human_number(value: f64, rounding: Rounding) -> String {
+    const UNITS: [&str; 4] = ["k", "M", "G", "T"];
+
+    let magnitude = value.abs();
+    let digits = match rounding {
+        _ if !value.is_finite() => return value.to_string(),
+        Rounding::Decimals(decimals) => return format!("{value:.0$}", decimals as usize),
+        Rounding::Significant(digits) => digits.max(1),
+    };
+    if magnitude >= 1e15 || (magnitude < 1e-3 && magnitude > 0.) {
+        return format!("{value:.0$e}", digits as usize - 1);
+    }
+
+    let mut scaled = value;
+    let mut unit = "";
+    let mut units = UNITS.into_iter();
+    // the unit follows the rounded value, 999_999 is 1M rather than 1000k
+    while Rounding::Significant(digits).apply(scaled).abs() >= 1e3 {
+        let Some(next) = units.next() else {
+            return format!("{value:.0$e}", digits as usize - 1);
+        };
+        scaled /= 1e3;
+        unit = next;
+    }
+    let decimals = Rounding::Significant(digits).decimals(scaled).max(0) as usize;
+    let mut formatted = format!("{scaled:.decimals$}");
+    if formatted.contains('.') {
+        formatted.truncate(formatted.trim_end_matches('0').trim_end_matches('.').len());
+    }
+    if formatted == "-0" {
+        formatted = "0".to_string();
+    }
+    formatted + unit
+}
+
+pub(crate) fn human_opt_number(value: Option<f64>, rounding: Rounding) -> String {
+    value
+        .map(|value| human_number(value, rounding))
+        .unwrap_or_else(|| "-".to_string())
+}
+
 fn display_opt_num(value: &Option<impl ToString>) -> String {
     value
         .as_ref()
         .map(|v| v.to_string())
         .unwrap_or_else(|| "N/A".to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rounding_digits_bounded() {
+        let parse = |args: &[&str]| {
+            CsvColStatsArgs::try_parse_from(["csv-col-stats", "a.csv"].iter().chain(args))
+                .map(|args| args.rounding())
+        };
+        assert_eq!(
+            parse(&["--precision", "17"]).unwrap(),
+            Some(Rounding::Decimals(17))
+        );
+        assert_eq!(
+            parse(&["--significant-digits", "17"]).unwrap(),
+            Some(Rounding::Significant(17))
+        );
+        assert!(parse(&["--precision", "18"]).is_err());
+        assert!(parse(&["--precision", "3000000000"]).is_err());
+        assert!(parse(&["--significant-digits", "0"]).is_err());
+        assert!(parse(&["--significant-digits", "18"]).is_err());
+    }
+
+    #[test]
+    fn test_stats_display() {
+        let stats = Stats {
+            count: 2,
+            non_numeric_count: 1,
+            min: Some(Number::Int(1)),
+            max: Some(Number::Int(3)),
+            mean: Some(2.),
+            quantiles: vec![Quantile {
+                quantile: 0.5,
+                value: Some(2.),
+            }],
+            ..Stats::default()
+        };
+        assert_eq!(
+            stats.to_string(),
+            "count=2 nulls=0 non_numeric=1 min=1 max=3 mean=2 median=- stddev=- p50=2"
+        );
+    }
+
+    #[test]
+    fn test_human_number() {
+        let human = |value| human_number(value, Rounding::HUMAN);
+        assert_eq!(human(0.), "0");
+        assert_eq!(human(2.), "2");
+        assert_eq!(human(21.666666), "21.67");
+        assert_eq!(human(-0.816496), "-0.8165");
+        assert_eq!(human(1234.), "1.234k");
+        assert_eq!(human(2_500_000.), "2.5M");
+        assert_eq!(human(999.96), "1k");
+        assert_eq!(human(999_949.), "999.9k");
+        assert_eq!(human(999_999.), "1M");
+        assert_eq!(human(-999_950.), "-1M");
+        assert_eq!(human(999_999_999_999_999.), "1.000e15");
+        assert_eq!(human_number(999_999., Rounding::Significant(2)), "1M");
+        assert_eq!(human(0.000012345), "1.234e-5");
+        assert_eq!(human_number(1234.5678, Rounding::Significant(2)), "1.2k");
+        assert_eq!(human_number(1234.5678, Rounding::Decimals(1)), "1234.6");
+        assert_eq!(human_count(1_234_567), "1,234,567");
+        assert_eq!(human_count(999), "999");
+    }
+}
@@ -1,69 +1,119 @@
-use std::thread;
+use std::{io, path::PathBuf, process::ExitCode, sync::mpsc, thread, time::Instant};
 
 use clap::Parser;
-use csv_col_stats::{Config, CsvColError, CsvColStatsArgs, TableView, parse_file};
-use tabled::{
-    Table,
-    settings::{Alignment, Style, object::Columns},
+use csv_col_stats::{
+    Config, CsvColError, CsvColStatsArgs, FileReport, Report, formatter, parse_file, parse_stream,
 };
 
-fn main() {
+/// File name reading from standard input.
+const STDIN: &str = "-";
+
+fn main() -> ExitCode {
     let args = CsvColStatsArgs::parse();
 
-    // TODO: binary could be optimize to accept either path to files or IO read
-    if args.files.is_empty() {
-        panic!("No CSV file provided, run with \"-h\" for help");
+    let stdin = PathBuf::from(STDIN);
+    let files = if args.files.is_empty() {
+        vec![stdin.clone()]
+    } else {
+        args.files.clone()
+    };
+    if files.iter().filter(|file| **file == stdin).count() > 1 {
+        eprintln!("Standard input (\"{STDIN}\") can only be read once");
+        return ExitCode::FAILURE;
     }
 
-    let files_names: Vec<String> = args
-        .files
+    let files_names: Vec<String> = files
         .iter()
         .map(|file| file.to_string_lossy().into_owned())
         .collect();
 
     // TODO: it's naive approach. It should balance budget per file
-    let budget_per_file = args.memory_budget / args.files.len();
+    let budget_per_file = args.memory_budget / files.len();
     let mut config: Config = Config::from(&args);
     config.median_config.memory_budget = budget_per_file;
+    if let Some(path) = &args.reject_file
+        && let Err(e) = config.open_reject_file(path)
+    {
+        eprintln!("{e}");
+        return ExitCode::FAILURE;
+    }
 
+    let format = args.output_format();
+    let rounding = args.rounding();
+    let file_count = files.len();
+    let (sender, receiver) = mpsc::channel();
     let mut handlers = Vec::new();
-    for file in args.files.clone() {
+    for (index, file) in files.into_iter().enumerate() {
         let config = config.clone();
-        handlers.push(thread::spawn(move || parse_file(file.as_path(), config)));
+        let sender = sender.clone();
+        handlers.push(thread::spawn(move || {
+            let start = Instant::now();
+            let output = if file.as_os_str() == STDIN {
+                parse_stream(io::stdin().lock(), config)
+            } else {
+                parse_file(file.as_path(), config)
+            };
+            // the receiver lives until every file is reported
+            let _ = sender.send((index, output, start.elapsed()));
+        }));
     }
+    drop(sender);
 
-    let mut result = Vec::new();
+    // files are reported as soon as they finish, a panicked thread never
+    // sends its result
+    let mut outputs: Vec<Option<FileReport>> = (0..file_count).map(|_| None).collect();
+    let mut exit_code = ExitCode::SUCCESS;
+    let mut formatter = formatter(format, rounding);
+    for (index, output, elapsed) in receiver {
+        let mut file = FileReport::new(files_names[index].clone(), output);
+        file.elapsed = elapsed;
+        if let Some(rounding) = rounding {
+            file.round(rounding);
+        }
+        if !report_diagnostics(&file) {
+            exit_code = ExitCode::FAILURE;
+        }
+        if let Err(e) = formatter.file(&mut io::stdout().lock(), &file) {
+            eprintln!("Can't write output: {e}");
+            return ExitCode::FAILURE;
+        }
+        outputs[index] = Some(file);
+    }
     for handler in handlers {
-        match handler.join() {
-            Ok(output) => result.push(output),
-            Err(_) => result.push(Err(CsvColError::ThreadPanic)),
-        };
+        let _ = handler.join();
     }
 
-    result
-        .into_iter()
-        .zip(files_names)
-        .for_each(|(output, file_name)| {
-            let output = output.unwrap();
+    let mut files = Vec::new();
+    for (output, file_name) in outputs.into_iter().zip(files_names) {
+        let file = output.unwrap_or_else(|| {
+            let file = FileReport::new(file_name, Err(CsvColError::ThreadPanic));
+            report_diagnostics(&file);
+            exit_code = ExitCode::FAILURE;
+            file
+        });
+        files.push(file);
+    }
+    let report = Report::new(files);
 
-            println!("File: {file_name}");
-            // TODO: multiple file support for correct JSON output requires redesign
-            if args.json {
-                let serialized_file = serde_json::to_string(&output).unwrap();
-                println!("{serialized_file}");
-            } else if args.table {
-                let mut rows: Vec<TableView> = output
-                    .into_iter()
-                    .map(|v| -> TableView { v.into() })
-                    .collect();
-                rows.sort_by(|a, b| a.column_name.cmp(&b.column_name));
-                let mut table = Table::new(rows);
-                table.with(Style::modern());
-                table.modify(Columns::first(), Alignment::right());
+    if let Err(e) = formatter.finish(&mut io::stdout().lock(), &report) {
+        eprintln!("Can't write output: {e}");
+        return ExitCode::FAILURE;
+    }
 
-                println!("{table}");
-            } else {
-                println!("{output:?}");
-            }
-        });
+    exit_code
+}
+
+/// Prints errors and warnings of `file` to stderr, returns `false` if the
+/// file failed.
+fn report_diagnostics(file: &FileReport) -> bool {
+    for warning in file.output.iter().flat_map(|output| &output.warnings) {
+        eprintln!("File: {}: warning: {warning}", file.path);
+    }
+    match &file.error {
+        Some(e) => {
+            eprintln!("File: {}: {e}", file.path);
+            false
+        }
+        None => true,
+    }
 }
@@ -1,15 +1,24 @@
-use crate::{CsvColError, MedianConfig, Stats};
+use crate::{CsvColError, InvalidValue, MedianConfig, NullValues, Number, Quantile, Stats};
 
 mod column;
+mod compression;
 mod file;
 mod median;
+mod moments;
+mod reject;
+mod sniff;
+mod spill;
+mod sum;
 
 pub(crate) use column::ColumnParseError;
-pub use file::parse_file;
+pub use file::{parse_file, parse_stream};
 use median::Median;
+use moments::Moments;
+pub use reject::RejectWriter;
+use sum::Sum;
 
-pub(in crate::parser) fn is_empty(bytes: &[u8]) -> bool {
-    bytes.is_empty() || bytes == b"NaN" || bytes == b"nan" || bytes == b"null" || bytes == b"N/A"
+pub(in crate::parser) fn is_empty(bytes: &[u8], null_values: &NullValues) -> bool {
+    bytes.is_empty() || null_values.contains(bytes)
 }
 
 pub(in crate::parser) fn trim_bytes(mut bytes: &[u8]) -> &[u8] {
@@ -32,12 +41,25 @@ pub(in crate::parser) fn trim_bytes(mut bytes: &[u8]) -> &[u8] {
     bytes
 }
 
+/// Cells of a column that didn't contribute a number.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(in crate::parser) struct CellCounts {
+    nulls: usize,
+    non_numeric: usize,
+}
+
 pub(in crate::parser) struct ColStats {
-    sum: i64,
+    sum: Sum,
     count: usize,
-    max: Option<i64>,
-    min: Option<i64>,
+    cells: CellCounts,
+    invalid_samples: Vec<InvalidValue>,
+    is_float: bool,
+    precision_loss: bool,
+    moments: Moments,
+    max: Option<Number>,
+    min: Option<Number>,
     median_approach: Median,
+    quantiles: Vec<f64>,
 }
 
 impl ColStats {
@@ -45,15 +67,43 @@ impl ColStats {
         Self {
             sum: Default::default(),
             count: Default::default(),
+            cells: Default::default(),
+            invalid_samples: Vec::new(),
+            is_float: false,
+            precision_loss: false,
+            moments: Default::default(),
             max: Default::default(),
             min: Default::default(),
             median_approach: Median::new(median_config.into()),
+            quantiles: median_config.quantiles.clone(),
         }
     }
 
-    fn update(&mut self, value: i64) {
-        // TODO: handle overflow
-        self.sum = self.sum.strict_add(value);
+    /// Converts integer accumulators to floating point, keeping everything
+    /// collected so far.
+    fn promote(&mut self) {
+        let to_float = |value: Number| Number::Float(value.as_f64());
+        self.is_float = true;
+        self.sum.promote();
+        self.max = self.max.map(to_float);
+        self.min = self.min.map(to_float);
+    }
+
+    fn update(&mut self, value: Number) {
+        let value = match (self.is_float, value) {
+            (false, Number::Float(_)) => {
+                self.promote();
+                value
+            }
+            (true, Number::Int(value)) => Number::Float(value as f64),
+            _ => value,
+        };
+
+        let exact = match value {
+            Number::Int(value) => self.sum.add_int(value),
+            Number::Float(value) => self.sum.add_float(value),
+        };
+        self.precision_loss |= !exact;
         self.count += 1;
         match self.max.as_ref() {
             Some(current_max) if current_max < &value => {
@@ -68,24 +118,45 @@ impl ColStats {
             _ => (),
         }
 
-        self.median_approach.add(value);
+        self.moments.add(value.as_f64());
+        self.median_approach.add(value.as_f64());
     }
 }
 
 impl TryInto<Stats> for ColStats {
     type Error = CsvColError;
 
-    fn try_into(self) -> std::result::Result<Stats, Self::Error> {
+    fn try_into(mut self) -> std::result::Result<Stats, Self::Error> {
+        // median and requested quantiles are answered together, so the
+        // external strategy merges its runs only once
+        let mut requested = vec![0.5];
+        requested.extend(&self.quantiles);
+        let mut values = self.median_approach.quantiles(&requested)?.into_iter();
+        let median = values.next().flatten();
+        let quantiles = self
+            .quantiles
+            .iter()
+            .zip(values)
+            .map(|(&quantile, value)| Quantile { quantile, value })
+            .collect();
+
         let stats = Stats {
+            count: self.count,
+            null_count: self.cells.nulls,
+            non_numeric_count: self.cells.non_numeric,
             max: self.max,
             min: self.min,
-            mean: if self.count > 0 {
-                let result = self.sum as f64 / self.count as f64;
-                Some((result * 100.0).round() / 100.0)
-            } else {
-                None
-            },
-            median: self.median_approach.calculate()?,
+            mean: self.sum.mean(self.count).or_else(|| self.moments.mean()),
+            median,
+            variance: self.moments.variance(),
+            sample_variance: self.moments.sample_variance(),
+            stddev: self.moments.variance().map(f64::sqrt),
+            sample_stddev: self.moments.sample_variance().map(f64::sqrt),
+            skewness: self.moments.skewness(),
+            kurtosis: self.moments.kurtosis(),
+            quantiles,
+            invalid_samples: self.invalid_samples,
+            precision_loss: self.precision_loss,
         };
         Ok(stats)
     }
@@ -1,8 +1,8 @@
 use thiserror::Error;
 
-use crate::MedianConfig;
 use crate::filter::Expression;
 use crate::parser::ColStats;
+use crate::{MedianConfig, Number};
 
 pub enum ColumnOption {
     Uninitialized,
@@ -16,6 +16,23 @@ pub enum ColumnOption {
 pub enum ColumnParseError {
     #[error("Can't parse number: {0}")]
     BadNumber(#[from] lexical_core::Error),
+
+    #[error("Number is not finite")]
+    NotFinite,
+}
+
+/// Parses raw field bytes as an integer, falling back to a float.
+///
+/// Infinite and NaN values (`inf`, `NAN`, `-Infinity`, ...) are rejected,
+/// a single one would otherwise poison every moment of the column.
+pub(in crate::parser) fn parse_number(field: &[u8]) -> Result<Number, ColumnParseError> {
+    match lexical_core::parse::<i64>(field) {
+        Ok(value) => Ok(Number::Int(value)),
+        Err(_) => match lexical_core::parse::<f64>(field)? {
+            value if value.is_finite() => Ok(Number::Float(value)),
+            _ => Err(ColumnParseError::NotFinite),
+        },
+    }
 }
 
 /// Parses a single CSV field and updates column statistics.
@@ -24,9 +41,11 @@ pub enum ColumnParseError {
 /// the column state in `stats` accordingly:
 ///
 /// - Initializes column statistics on the first successfully parsed value.
-/// - Updates existing numeric statistics.
+/// - Updates existing numeric statistics, promoting an integer column to
+///   floating point on the first decimal value.
 /// - Applies an optional filter expression when present.
-/// - Ignores empty or non-numeric fields until the column becomes numeric.
+/// - Leaves the column uninitialized on non-numeric fields until a number
+///   is seen, so text in early rows doesn't hide a numeric column.
 ///
 /// # Parameters
 /// - `field`: Raw CSV field bytes (may contain whitespace).
@@ -41,14 +60,14 @@ pub fn parse_column(
     median_config: &MedianConfig,
     stats: &mut ColumnOption,
 ) -> Result<(), ColumnParseError> {
-    match lexical_core::parse::<i64>(field) {
+    match parse_number(field) {
         Ok(value) => {
             match stats {
                 ColumnOption::Number(stats) => {
                     stats.update(value);
                 }
                 ColumnOption::FilteredNumber(stats, expression) => {
-                    if expression.unchacked_validate(&value) {
+                    if expression.matches_value(&value) {
                         stats.update(value);
                     }
                 }
@@ -60,7 +79,7 @@ pub fn parse_column(
                 // if filtered column is not number it's going to fail later
                 ColumnOption::UninitializedWithFilter(expression) => {
                     let mut new_stats = ColStats::new(median_config);
-                    if expression.unchacked_validate(&value) {
+                    if expression.matches_value(&value) {
                         new_stats.update(value);
                     }
                     *stats = ColumnOption::FilteredNumber(new_stats, expression.clone())
@@ -69,12 +88,8 @@ pub fn parse_column(
             }
         }
         Err(e) => {
-            match stats {
-                ColumnOption::Number(_) | ColumnOption::FilteredNumber(_, _) => {
-                    // TODO: remove field_index
-                    return Err(ColumnParseError::BadNumber(e));
-                }
-                value => *value = ColumnOption::Ignored,
+            if let ColumnOption::Number(_) | ColumnOption::FilteredNumber(_, _) = stats {
+                return Err(e);
             }
         }
     }
@@ -86,6 +101,8 @@ mod tests {
     use std::str::FromStr;
 
     use super::*;
+    use crate::Number::{Float, Int};
+    use crate::parser::Sum;
     use ColumnOption::*;
 
     #[test]
@@ -95,11 +112,11 @@ mod tests {
         let median_config = MedianConfig::default();
         parse_column(b"16", &median_config, &mut column_stats[1]).unwrap();
 
-        match column_stats.get(1).unwrap() {
+        match column_stats.get_mut(1).unwrap() {
             Number(stat) => {
-                assert_eq!(stat.min, Some(16));
-                assert_eq!(stat.max, Some(16));
-                assert_eq!(stat.sum, 16);
+                assert_eq!(stat.min, Some(Int(16)));
+                assert_eq!(stat.max, Some(Int(16)));
+                assert_eq!(stat.sum, Sum::Int(16));
                 assert_eq!(stat.count, 1);
                 assert_eq!(stat.median_approach.calculate().unwrap(), Some(16.));
             }
@@ -108,11 +125,11 @@ mod tests {
 
         parse_column(b"4", &median_config, &mut column_stats[1]).unwrap();
 
-        match column_stats.get(1).unwrap() {
+        match column_stats.get_mut(1).unwrap() {
             Number(stat) => {
-                assert_eq!(stat.min, Some(4));
-                assert_eq!(stat.max, Some(16));
-                assert_eq!(stat.sum, 20);
+                assert_eq!(stat.min, Some(Int(4)));
+                assert_eq!(stat.max, Some(Int(16)));
+                assert_eq!(stat.sum, Sum::Int(20));
                 assert_eq!(stat.count, 2);
                 assert_eq!(stat.median_approach.calculate().unwrap(), Some(10.));
             }
@@ -121,11 +138,11 @@ mod tests {
 
         parse_column(b"2", &median_config, &mut column_stats[1]).unwrap();
 
-        match column_stats.get(1).unwrap() {
+        match column_stats.get_mut(1).unwrap() {
             Number(stat) => {
-                assert_eq!(stat.min, Some(2));
-                assert_eq!(stat.max, Some(16));
-                assert_eq!(stat.sum, 22);
+                assert_eq!(stat.min, Some(Int(2)));
+                assert_eq!(stat.max, Some(Int(16)));
+                assert_eq!(stat.sum, Sum::Int(22));
                 assert_eq!(stat.count, 3);
                 assert_eq!(stat.median_approach.calculate().unwrap(), Some(4.));
             }
@@ -133,9 +150,83 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_float_promotion_parse_column() {
+        let mut column_stats = [Uninitialized];
+
+        let median_config = MedianConfig::default();
+        parse_column(b"4", &median_config, &mut column_stats[0]).unwrap();
+        parse_column(b"10", &median_config, &mut column_stats[0]).unwrap();
+
+        match &column_stats[0] {
+            Number(stat) => {
+                assert_eq!(stat.sum, Sum::Int(14));
+                assert_eq!(stat.max, Some(Int(10)));
+            }
+            _ => panic!("Stat should be initialized"),
+        }
+
+        parse_column(b"0.5", &median_config, &mut column_stats[0]).unwrap();
+        parse_column(b"7", &median_config, &mut column_stats[0]).unwrap();
+
+        match &mut column_stats[0] {
+            Number(stat) => {
+                assert_eq!(stat.min, Some(Float(0.5)));
+                assert_eq!(stat.max, Some(Float(10.)));
+                assert_eq!(stat.sum.as_f64(), 21.5);
+                assert_eq!(stat.count, 4);
+                assert_eq!(stat.median_approach.calculate().unwrap(), Some(5.5));
+            }
+            _ => panic!("Stat should be initialized"),
+        }
+    }
+
+    #[test]
+    fn test_large_int_parse_column() {
+        let mut column_stats = [Uninitialized];
+
+        let median_config = MedianConfig::default();
+        parse_column(b"9223372036854775807", &median_config, &mut column_stats[0]).unwrap();
+        parse_column(b"9223372036854775807", &median_config, &mut column_stats[0]).unwrap();
+
+        match &column_stats[0] {
+            Number(stat) => {
+                assert_eq!(stat.sum, Sum::Int(2 * i64::MAX as i128));
+                assert!(!stat.precision_loss);
+            }
+            _ => panic!("Stat should be initialized"),
+        }
+    }
+
+    #[test]
+    fn test_non_finite_parse_column() {
+        let mut column_stats = [Uninitialized];
+
+        let median_config = MedianConfig::default();
+        parse_column(b"1", &median_config, &mut column_stats[0]).unwrap();
+        for field in ["inf", "INF", "-Infinity", "NAN", "nAn", "1e400"] {
+            let result = parse_column(field.as_bytes(), &median_config, &mut column_stats[0]);
+            assert!(
+                matches!(result, Err(ColumnParseError::NotFinite)),
+                "{field} should be rejected"
+            );
+        }
+        parse_column(b"2.5", &median_config, &mut column_stats[0]).unwrap();
+
+        match &column_stats[0] {
+            Number(stat) => {
+                assert_eq!(stat.count, 2);
+                assert_eq!(stat.min, Some(Float(1.)));
+                assert_eq!(stat.max, Some(Float(2.5)));
+                assert_eq!(stat.moments.mean(), Some(1.75));
+            }
+            _ => panic!("Stat should be initialized"),
+        }
+    }
+
     #[test]
     fn test_text_parse_column() {
-        let mut column_stats = vec![Uninitialized, Uninitialized];
+        let mut column_stats = [Uninitialized, Uninitialized];
 
         let median_config = MedianConfig::default();
 
@@ -161,7 +252,7 @@ mod tests {
 
         match &column_stats[2] {
             Number(value) => {
-                assert_eq!(value.max, Some(120))
+                assert_eq!(value.max, Some(Int(120)))
             }
             _ => panic!("field should be initialized"),
         }
@@ -172,7 +263,11 @@ mod tests {
         let median_config = MedianConfig::default();
         let mut column_stats = [
             Uninitialized,
-            UninitializedWithFilter(Expression::from_str("value > 1").unwrap()),
+            UninitializedWithFilter(
+                Expression::from_str("value > 1")
+                    .unwrap()
+                    .for_column(1, "value"),
+            ),
         ];
 
         parse_column(b"120", &median_config, &mut column_stats[1]).unwrap();
@@ -180,7 +275,7 @@ mod tests {
         // let item = column_stats.into_iter().nth(1).unwrap();
         match &column_stats[1] {
             FilteredNumber(s, _) => {
-                assert_eq!(s.max, Some(120))
+                assert_eq!(s.max, Some(Int(120)))
             }
             _ => panic!("field should be initialized"),
         }
@@ -191,7 +286,11 @@ mod tests {
         let median_config = MedianConfig::default();
         let mut column_stats = [
             Uninitialized,
-            UninitializedWithFilter(Expression::from_str("value > 10").unwrap()),
+            UninitializedWithFilter(
+                Expression::from_str("value > 10")
+                    .unwrap()
+                    .for_column(1, "value"),
+            ),
         ];
 
         parse_column(b"5", &median_config, &mut column_stats[1]).unwrap();
@@ -210,10 +309,15 @@ mod tests {
     fn test_filter_out_initialized_parse_column() {
         let median_config = MedianConfig::default();
         let mut stat = ColStats::new(&median_config);
-        stat.update(20);
+        stat.update(Int(20));
         let mut column_stats = [
             Uninitialized,
-            FilteredNumber(stat, Expression::from_str("value > 10").unwrap()),
+            FilteredNumber(
+                stat,
+                Expression::from_str("value > 10")
+                    .unwrap()
+                    .for_column(1, "value"),
+            ),
         ];
 
         parse_column(b"5", &median_config, &mut column_stats[1]).unwrap();
@@ -221,7 +325,7 @@ mod tests {
         // let item = column_stats.into_iter().nth(1).unwrap();
         match &column_stats[1] {
             FilteredNumber(s, _) => {
-                assert_eq!(s.max, Some(20));
+                assert_eq!(s.max, Some(Int(20)));
                 assert_eq!(s.count, 1);
             }
             _ => panic!("field should be initialized"),
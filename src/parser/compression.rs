@@ -0,0 +1,211 @@
+use std::io::{self, BufRead, BufReader, Cursor, Read};
+
+use crate::{CsvColError, Result};
+
+/// Compression format of an input, detected from its magic bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+    Bzip2,
+    Xz,
+}
+
+/// Bytes needed to tell the formats apart, bzip2 being the longest.
+const HEADER_LEN: usize = 10;
+
+impl Compression {
+    /// Detects the format from the first bytes of the input, regardless of
+    /// the file extension.
+    ///
+    /// bzip2 also needs its block size digit and the magic of the first
+    /// block (or of the end of an empty stream), `BZh` alone is a plausible
+    /// CSV header.
+    pub fn detect(header: &[u8]) -> Self {
+        if header.starts_with(&[0x1f, 0x8b]) {
+            Self::Gzip
+        } else if header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Self::Zstd
+        } else if let [b'B', b'Z', b'h', b'1'..=b'9', magic @ ..] = header
+            && (magic.starts_with(&[0x31, 0x41, 0x59, 0x26, 0x53, 0x59])
+                || magic.starts_with(&[0x17, 0x72, 0x45, 0x38, 0x50, 0x90]))
+        {
+            Self::Bzip2
+        } else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Self::Xz
+        } else {
+            Self::None
+        }
+    }
+
+    /// Reads the first bytes of `reader` to detect its format, returning it
+    /// with a reader starting over from the first byte.
+    ///
+    /// # Errors
+    /// Returns an error if reading fails.
+    pub fn read<R: Read>(mut reader: R) -> io::Result<(Self, impl BufRead)> {
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        // a single read may return fewer bytes than are available
+        reader
+            .by_ref()
+            .take(HEADER_LEN as u64)
+            .read_to_end(&mut header)?;
+        let compression = Self::detect(&header);
+        Ok((
+            compression,
+            BufReader::new(Cursor::new(header).chain(reader)),
+        ))
+    }
+
+    /// Wraps `reader` in a decoder for this format.
+    ///
+    /// # Errors
+    /// Returns an error if support for the format was not compiled in.
+    pub fn decoder<'a>(&self, reader: impl BufRead + 'a) -> Result<Box<dyn Read + 'a>> {
+        let decoder: Box<dyn Read + 'a> = match self {
+            Self::None => Box::new(reader),
+            #[cfg(feature = "gzip")]
+            Self::Gzip => Box::new(flate2::bufread::MultiGzDecoder::new(reader)),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => Box::new(
+                zstd::stream::read::Decoder::with_buffer(reader)
+                    .map_err(|e| CsvColError::Io("zstd stream".to_string(), e))?,
+            ),
+            #[cfg(feature = "bzip2")]
+            Self::Bzip2 => Box::new(bzip2::bufread::MultiBzDecoder::new(reader)),
+            #[cfg(feature = "xz")]
+            Self::Xz => Box::new(xz2::bufread::XzDecoder::new_multi_decoder(reader)),
+            #[cfg(not(feature = "gzip"))]
+            Self::Gzip => return Err(CsvColError::UnsupportedCompression("gzip")),
+            #[cfg(not(feature = "zstd"))]
+            Self::Zstd => return Err(CsvColError::UnsupportedCompression("zstd")),
+            #[cfg(not(feature = "bzip2"))]
+            Self::Bzip2 => return Err(CsvColError::UnsupportedCompression("bzip2")),
+            #[cfg(not(feature = "xz"))]
+            Self::Xz => return Err(CsvColError::UnsupportedCompression("xz")),
+        };
+        Ok(decoder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    const CSV: &str = "id,value\n1,10\n2,20\n";
+
+    fn decode(bytes: &[u8]) -> String {
+        let compression = Compression::detect(bytes);
+        let mut output = String::new();
+        compression
+            .decoder(bytes)
+            .unwrap()
+            .read_to_string(&mut output)
+            .unwrap();
+        output
+    }
+
+    #[test]
+    fn test_detect_plain() {
+        assert_eq!(Compression::detect(CSV.as_bytes()), Compression::None);
+        assert_eq!(Compression::detect(b""), Compression::None);
+        assert_eq!(decode(CSV.as_bytes()), CSV);
+    }
+
+    #[test]
+    fn test_detect_bzip2_like_header() {
+        for header in ["BZh,value\n1,2\n", "BZh9,value\n", "BZh1\n", "BZh"] {
+            assert_eq!(
+                Compression::detect(header.as_bytes()),
+                Compression::None,
+                "{header:?}"
+            );
+        }
+        assert_eq!(Compression::detect(b"BZh91AY&SY\x00"), Compression::Bzip2);
+    }
+
+    /// Reader returning a single byte per call.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let count = buf.len().min(self.0.len()).min(1);
+            buf[..count].copy_from_slice(&self.0[..count]);
+            self.0 = &self.0[count..];
+            Ok(count)
+        }
+    }
+
+    #[test]
+    fn test_read_short_reads() {
+        let bytes = [0x28, 0xb5, 0x2f, 0xfd, 0x00, 0x01];
+        let (compression, mut reader) = Compression::read(Trickle(&bytes)).unwrap();
+        assert_eq!(compression, Compression::Zstd);
+        let mut output = Vec::new();
+        reader.read_to_end(&mut output).unwrap();
+        assert_eq!(output, bytes);
+
+        let (compression, mut reader) = Compression::read(Trickle(CSV.as_bytes())).unwrap();
+        assert_eq!(compression, Compression::None);
+        let mut output = String::new();
+        reader.read_to_string(&mut output).unwrap();
+        assert_eq!(output, CSV);
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_gzip() {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), Default::default());
+        encoder.write_all(CSV.as_bytes()).unwrap();
+        let bytes = encoder.finish().unwrap();
+
+        assert_eq!(Compression::detect(&bytes), Compression::Gzip);
+        assert_eq!(decode(&bytes), CSV);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd() {
+        let bytes = zstd::encode_all(CSV.as_bytes(), 0).unwrap();
+
+        assert_eq!(Compression::detect(&bytes), Compression::Zstd);
+        assert_eq!(decode(&bytes), CSV);
+    }
+
+    #[cfg(feature = "bzip2")]
+    #[test]
+    fn test_bzip2() {
+        use std::io::Write;
+
+        let mut encoder = bzip2::write::BzEncoder::new(Vec::new(), Default::default());
+        encoder.write_all(CSV.as_bytes()).unwrap();
+        let bytes = encoder.finish().unwrap();
+
+        assert_eq!(Compression::detect(&bytes), Compression::Bzip2);
+        assert_eq!(decode(&bytes), CSV);
+
+        let empty = bzip2::write::BzEncoder::new(Vec::new(), Default::default())
+            .finish()
+            .unwrap();
+        assert_eq!(Compression::detect(&empty), Compression::Bzip2);
+        assert_eq!(decode(&empty), "");
+    }
+
+    #[cfg(feature = "xz")]
+    #[test]
+    fn test_xz() {
+        use std::io::Write;
+
+        let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+        encoder.write_all(CSV.as_bytes()).unwrap();
+        let bytes = encoder.finish().unwrap();
+
+        assert_eq!(Compression::detect(&bytes), Compression::Xz);
+        assert_eq!(decode(&bytes), CSV);
+    }
+}
@@ -1,17 +1,27 @@
 use std::{
+    cell::RefCell,
+    collections::HashSet,
     fs::File,
-    io::{BufReader, Read},
+    io::{Cursor, Read},
     os::unix::fs::MetadataExt,
     path::Path,
+    rc::Rc,
 };
 
-use csv::Reader;
+use csv::{ByteRecord, ReaderBuilder};
 
 use crate::{
-    Config, CsvColError, Output, Result, Stats,
+    Config, CsvColError, Dialect, FilterMode, InvalidValue, NullValues, OnError, Output,
+    RaggedRows, Result, RowCounts, Stats, Terminator,
+    filter::{Value, refers_to},
     parser::{
-        column::{ColumnOption, parse_column},
-        is_empty, trim_bytes,
+        CellCounts,
+        column::{ColumnOption, parse_column, parse_number},
+        compression::Compression,
+        is_empty,
+        reject::{RawInput, Recording},
+        sniff::sniff,
+        trim_bytes,
     },
 };
 
@@ -21,8 +31,14 @@ use crate::{
 /// strategy based on the file size and `config.median_config`, and delegates
 /// parsing to [`parse_reader`].
 ///
-/// The median strategy (exact vs approximate) is selected automatically by
-/// comparing the configured memory budget against the input file size.
+/// Files compressed with gzip, zstd, bzip2 or xz are detected by their magic
+/// bytes and decompressed on the fly.
+///
+/// The median is calculated exactly in memory when the input file fits in
+/// the configured memory budget, otherwise `config.median_config.fallback`
+/// selects between an approximation and an exact spill to disk. Compressed
+/// files are handled like streams (see [`parse_stream`]), as their size on
+/// disk understates the data.
 ///
 /// # Parameters
 /// - `path`: Path to the CSV file to parse.
@@ -35,7 +51,8 @@ use crate::{
 /// # Errors
 /// Returns an error if:
 /// - The file cannot be opened or read.
-/// - CSV parsing fails.
+/// - CSV parsing fails and `config.data_config.on_error` isn't
+///   [`OnError::Skip`].
 /// - A column previously identified as numeric encounters invalid data.
 pub fn parse_file(path: &Path, mut config: Config) -> Result<Output> {
     let file =
@@ -45,11 +62,50 @@ pub fn parse_file(path: &Path, mut config: Config) -> Result<Output> {
         .map_err(|e| CsvColError::Io(path.to_string_lossy().into_owned(), e))?
         .size();
 
-    config.median_config.exact_median = config.median_config.memory_budget >= file_size as usize;
+    let (compression, reader) = Compression::read(file)
+        .map_err(|e| CsvColError::Io(path.to_string_lossy().into_owned(), e))?;
+
+    if compression == Compression::None {
+        config.median_config.exact_median =
+            config.median_config.memory_budget >= file_size as usize;
+    } else {
+        // compressed size understates the data, treat it like a stream
+        config.median_config.exact_median = true;
+        config.median_config.streaming = true;
+    }
+    if let Some(writer) = &mut config.data_config.reject_writer {
+        *writer = writer.for_source(path.to_string_lossy());
+    }
+
+    into_output(parse_reader(compression.decoder(reader)?, config)?)
+}
+
+/// Parses CSV data of unknown size, e.g. standard input, and computes column
+/// statistics.
+///
+/// Without a size to compare against the memory budget, medians start exact
+/// in memory and switch to `config.median_config.fallback` once a column
+/// outgrows its share of the budget. Compressed input is detected and
+/// decompressed like in [`parse_file`].
+///
+/// # Errors
+/// Same as [`parse_file`], except for opening the file.
+pub fn parse_stream(reader: impl Read, mut config: Config) -> Result<Output> {
+    config.median_config.exact_median = true;
+    config.median_config.streaming = true;
+    if let Some(writer) = &mut config.data_config.reject_writer {
+        *writer = writer.for_source("-");
+    }
 
-    let reader = BufReader::new(file);
+    let (compression, reader) =
+        Compression::read(reader).map_err(|e| CsvColError::Io("-".to_string(), e))?;
 
-    let columns = parse_reader(reader, config)?
+    into_output(parse_reader(compression.decoder(reader)?, config)?)
+}
+
+fn into_output(parsed: ParsedReader) -> Result<Output> {
+    let columns = parsed
+        .columns
         .into_iter()
         .flat_map(|(header, col)| match col {
             ColumnOption::FilteredNumber(col, _) | ColumnOption::Number(col) => {
@@ -63,95 +119,482 @@ pub fn parse_file(path: &Path, mut config: Config) -> Result<Output> {
         })
         .collect::<Result<Vec<_>>>()?;
 
-    Ok(columns.into_iter().collect())
+    Ok(Output {
+        dialect: parsed.dialect,
+        detected_null_values: parsed.detected_null_values,
+        warnings: parsed.warnings,
+        row_counts: parsed.row_counts,
+        columns: columns.into_iter().collect(),
+    })
+}
+
+pub(in crate::parser) fn reader_builder(dialect: &Dialect) -> ReaderBuilder {
+    let mut builder = ReaderBuilder::new();
+    builder
+        .flexible(true)
+        .has_headers(dialect.has_header)
+        .delimiter(dialect.delimiter)
+        .quote(dialect.quote)
+        .escape(dialect.escape)
+        .comment(dialect.comment)
+        .double_quote(dialect.double_quote)
+        .terminator(match dialect.terminator {
+            Terminator::Crlf => csv::Terminator::CRLF,
+            Terminator::Byte(byte) => csv::Terminator::Any(byte),
+        });
+    builder
+}
+
+/// Column states and row counts produced by [`parse_reader`].
+pub struct ParsedReader {
+    /// `(column_name, ColumnOption)` pairs, preserving CSV header order.
+    pub columns: Vec<(String, ColumnOption)>,
+    pub row_counts: RowCounts,
+    /// Dialect the data was read with.
+    pub dialect: Dialect,
+    /// Null tokens added by the sniffer.
+    pub detected_null_values: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Renames repeated column names to `name#2`, `name#3`, ..., skipping names
+/// already taken, and returns a warning for every renamed column.
+fn disambiguate(headers: &mut [String]) -> Vec<String> {
+    let mut taken: HashSet<String> = headers.iter().cloned().collect();
+    let mut seen = HashSet::new();
+    let mut warnings = Vec::new();
+    for (index, header) in headers.iter_mut().enumerate() {
+        if seen.insert(header.clone()) {
+            continue;
+        }
+        let renamed = (2..)
+            .map(|n| format!("{header}#{n}"))
+            .find(|name| !taken.contains(name))
+            .unwrap_or_default();
+        warnings.push(format!(
+            "duplicate column '{header}' at index {index} renamed to '{renamed}'"
+        ));
+        taken.insert(renamed.clone());
+        seen.insert(renamed.clone());
+        *header = renamed;
+    }
+    warnings
+}
+
+/// Value of a field as seen by row filters, missing fields and null tokens
+/// are null.
+fn filter_value<'a>(field: Option<&'a [u8]>, null_values: &NullValues) -> Value<'a> {
+    match field.map(trim_bytes) {
+        Some(field) if !is_empty(field, null_values) => Value {
+            text: std::str::from_utf8(field).ok(),
+            number: parse_number(field).ok(),
+            ..Value::NULL
+        },
+        _ => Value::NULL,
+    }
 }
 
 /// Parses CSV data from a reader and computes per-column statistics.
 ///
-/// This function reads CSV records from `reader`, inspects each column,
-/// and incrementally builds column statistics according to `config`.
+/// This function reads CSV records from `reader` using the dialect in
+/// `config.data_config.dialect`, inspects each column, and incrementally
+/// builds column statistics according to `config`.
+///
+/// When `config.data_config.sniff` is set, the dialect is instead inferred
+/// from the first `sample_size` bytes, with explicitly configured settings
+/// taking precedence, and detected null tokens are added to
+/// `config.data_config.null_values`.
+///
+/// Columns are named after `config.data_config.names`, then after the header
+/// row, or `col_0..col_n` for inputs without one. Repeated names are renamed
+/// to `name#2`, `name#3`, ... with a warning, so ignored columns and filters
+/// referencing a repeated name only match its first occurrence.
 ///
 /// Column behavior:
-/// - Columns listed in `config.data_config.ignore_columns` are ignored.
-/// - Columns matching a filter expression are conditionally updated.
+/// - Columns listed in `config.data_config.ignore_columns`, by name or by
+///   zero-based index like `#3`, are ignored.
+/// - With [`FilterMode::Row`], rows not matching `config.data_config.filter`
+///   are skipped for every column after ragged rows are handled. With
+///   [`FilterMode::Column`] only values of the columns the filter references
+///   are conditionally updated.
 /// - Columns are initialized as numeric on the first successfully parsed value.
-/// - Empty or non-numeric values are ignored until a column becomes numeric.
-/// - Once a column is classified as numeric, subsequent parse errors are reported.
+/// - Non-numeric values seen before a column becomes numeric leave it
+///   uninitialized. Text in such a column never fails the file, even once a
+///   number classifies it, so a text column with a stray number is counted
+///   like with [`OnError::Skip`] when `config.data_config.on_error` is
+///   [`OnError::Fail`], and by the policy otherwise.
+/// - Empty cells and cells matching `config.data_config.null_values` (after
+///   trimming whitespace) are counted as nulls.
+/// - Once a column is classified as numeric, subsequent parse errors are
+///   handled according to `config.data_config.on_error`: reported, counted as
+///   non-numeric or counted as null. Skipped cells are sampled up to
+///   `config.data_config.error_samples` per column.
+/// - Rows with rejected cells are written to `config.data_config.reject_writer`
+///   when present, with their line number and original bytes.
+/// - With [`OnError::Skip`], a CSV error stops reading with a warning and is
+///   written to the reject writer instead of failing the file.
 ///
-/// Median calculation strategy (exact vs approximate) is determined by
-/// `config.median_config`.
+/// Median calculation strategy (exact, approximate or spilling to disk) is
+/// determined by `config.median_config`, whose memory budget is split evenly
+/// between the columns that are not ignored.
 ///
 /// # Parameters
 /// - `reader`: Any type implementing [`std::io::Read`] (e.g. file, buffer, cursor).
 /// - `config`: Parsing and aggregation configuration.
 ///
+/// Rows with a different number of fields than the header are handled
+/// according to `config.data_config.ragged`.
+///
 /// # Returns
-/// A vector of `(column_name, ColumnOption)` pairs, preserving CSV header order.
+/// Column states in CSV header order, with row counts.
 ///
 /// # Errors
 /// Returns an error if:
-/// - CSV parsing fails.
-/// - A column previously identified as numeric encounters a non-numeric value.
-pub fn parse_reader(reader: impl Read, config: Config) -> Result<Vec<(String, ColumnOption)>> {
-    let mut csv_reader = Reader::from_reader(reader);
+/// - The sample for sniffing can't be read.
+/// - The filter references a column that doesn't exist, in either mode.
+/// - A filter condition combines two columns with [`FilterMode::Column`].
+/// - CSV parsing fails and `config.data_config.on_error` isn't
+///   [`OnError::Skip`].
+/// - A row is ragged and `config.data_config.ragged` is [`RaggedRows::Error`].
+/// - A column previously identified as numeric encounters a non-numeric value
+///   and `config.data_config.on_error` is [`OnError::Fail`].
+pub fn parse_reader(mut reader: impl Read, mut config: Config) -> Result<ParsedReader> {
+    let mut sample = Vec::new();
+    let mut detected_null_values = Vec::new();
+    if let Some(sniff_config) = config.data_config.sniff {
+        let sample_size = sniff_config.sample_size as u64;
+        reader
+            .by_ref()
+            .take(sample_size)
+            .read_to_end(&mut sample)
+            .map_err(|e| CsvColError::Io("sample".to_string(), e))?;
 
-    let headers: Vec<String> = csv_reader
+        let complete = (sample.len() as u64) < sample_size;
+        let sniffed = sniff(&sample, complete, &sniff_config.overrides);
+        config.data_config.dialect = sniffed.dialect;
+        for token in &sniffed.null_values {
+            config.data_config.null_values.insert(token);
+        }
+        detected_null_values = sniffed.null_values;
+    }
+    let dialect = config.data_config.dialect;
+    // rejected records are written as they were read
+    let raw_input = config
+        .data_config
+        .reject_writer
+        .as_ref()
+        .map(|_| Rc::new(RefCell::new(RawInput::default())));
+    // the sample was consumed from `reader`, so it's read again first
+    let mut csv_reader = reader_builder(&dialect).from_reader(Recording {
+        inner: Cursor::new(sample).chain(reader),
+        raw: raw_input.clone(),
+    });
+
+    let names = &config.data_config.names;
+    let mut headers: Vec<String> = csv_reader
         .headers()?
         .iter()
-        .map(ToOwned::to_owned)
+        .enumerate()
+        .map(|(index, header)| match names.get(index) {
+            Some(name) => name.clone(),
+            None if dialect.has_header => header.to_owned(),
+            None => format!("col_{index}"),
+        })
         .collect();
+    let mut warnings = disambiguate(&mut headers);
 
-    let mut column_stats: Vec<ColumnOption> = Vec::with_capacity(headers.len());
-    for header in headers.iter() {
-        if config
+    let is_ignored = |index: usize, header: &str| {
+        config
             .data_config
             .ignore_columns
             .iter()
-            .any(|v| v == header)
-        {
+            .any(|reference| refers_to(reference, index, header))
+    };
+
+    // spilled columns buffer values independently, so they share the budget
+    let budget_columns = headers
+        .iter()
+        .enumerate()
+        .filter(|(index, header)| !is_ignored(*index, header))
+        .count();
+    config.median_config.memory_budget /= budget_columns.max(1);
+
+    let mut column_stats: Vec<ColumnOption> = Vec::with_capacity(headers.len());
+    for (index, header) in headers.iter().enumerate() {
+        if is_ignored(index, header) {
             column_stats.push(ColumnOption::Ignored);
-        } else if let Some(filter) = config.data_config.filter.clone()
-            && filter.check_by_name(header)
+        } else if config.data_config.filter_mode == FilterMode::Column
+            && let Some(filter) = &config.data_config.filter
+            && filter.check_column(index, header)
         {
-            column_stats.push(ColumnOption::UninitializedWithFilter(filter));
+            column_stats.push(ColumnOption::UninitializedWithFilter(
+                filter.for_column(index, header),
+            ));
         } else {
             column_stats.push(ColumnOption::Uninitialized);
         }
     }
 
-    for (row_index, row) in csv_reader.byte_records().enumerate() {
-        let row: csv::ByteRecord = row?;
+    // resolved in both modes, so a missing column is an error in column mode too
+    let row_filter = match &config.data_config.filter {
+        Some(filter) => {
+            let resolved = filter.resolve(&headers)?;
+            if config.data_config.filter_mode == FilterMode::Column
+                && let Some((first, second)) = resolved.cross_column()
+            {
+                return Err(CsvColError::CrossColumnFilter(
+                    headers[first].clone(),
+                    headers[second].clone(),
+                ));
+            }
+            (config.data_config.filter_mode == FilterMode::Row).then_some(resolved)
+        }
+        None => None,
+    };
+
+    // counted outside of `ColStats`, nulls may come before a column is
+    // known to be numeric
+    let mut cell_counts = vec![CellCounts::default(); headers.len()];
+    let mut invalid_samples: Vec<Vec<InvalidValue>> = vec![Vec::new(); headers.len()];
+    // columns with text before their first number aren't expected to hold
+    // only numbers, their later text is counted instead of failing the file
+    let mut held_text = vec![false; headers.len()];
+
+    let reject_writer = config.data_config.reject_writer.as_ref();
+    let mut reject_reasons = Vec::new();
+    let mut row_counts = RowCounts::default();
+
+    let mut row = ByteRecord::new();
+    for row_index in 0usize.. {
+        let start = csv_reader.position().clone();
+        if let Some(raw) = &raw_input {
+            raw.borrow_mut().discard(start.byte());
+        }
+        match csv_reader.read_byte_record(&mut row) {
+            Ok(true) => (),
+            Ok(false) => break,
+            // the rest of a broken stream can't be trusted, keep what was read
+            Err(e) if config.data_config.on_error == OnError::Skip => {
+                let line = match (reject_writer, &raw_input) {
+                    (Some(writer), Some(raw)) => {
+                        let raw = raw.borrow();
+                        let (line, record) = raw.record(&start, u64::MAX, &dialect);
+                        writer.write(line, &e.to_string(), record)?;
+                        line
+                    }
+                    _ => start.line(),
+                };
+                warnings.push(format!("Stopped reading at line {line}: {e}"));
+                break;
+            }
+            Err(e) => return Err(e.into()),
+        }
+        row_counts.rows += 1;
 
-        for (field_index, field) in row.iter().enumerate() {
+        let end = csv_reader.position().byte();
+        let reject = |reason: &str| -> Result<()> {
+            if let (Some(writer), Some(raw), Some(start)) =
+                (reject_writer, &raw_input, row.position())
+            {
+                let raw = raw.borrow();
+                let (line, record) = raw.record(start, end, &dialect);
+                writer.write(line, reason, record)?;
+            }
+            Ok(())
+        };
+
+        if row.len() != headers.len() {
+            row_counts.ragged_rows += 1;
+            let ragged = config.data_config.ragged;
+            if matches!(ragged, RaggedRows::Error | RaggedRows::Skip) {
+                reject(&format!(
+                    "expected {} fields, found {}",
+                    headers.len(),
+                    row.len()
+                ))?;
+            }
+            match ragged {
+                RaggedRows::Error => {
+                    if let Some(writer) = reject_writer {
+                        writer.flush()?;
+                    }
+                    return Err(CsvColError::RaggedRow(row_index, row.len(), headers.len()));
+                }
+                RaggedRows::Skip => continue,
+                RaggedRows::Pad | RaggedRows::Truncate => (),
+            }
+        }
+
+        let null_values = &config.data_config.null_values;
+        if let Some(filter) = &row_filter
+            && !filter.matches_row(|index| filter_value(row.get(index), null_values))
+        {
+            row_counts.filtered_rows += 1;
+            continue;
+        }
+
+        if config.data_config.ragged == RaggedRows::Pad {
+            for (column, cells) in column_stats
+                .iter()
+                .zip(cell_counts.iter_mut())
+                .skip(row.len())
+            {
+                if !matches!(column, ColumnOption::Ignored) {
+                    cells.nulls += 1;
+                }
+            }
+        }
+
+        for (field_index, field) in row.iter().take(headers.len()).enumerate() {
             if let ColumnOption::Ignored = column_stats[field_index] {
                 continue;
             }
-            if is_empty(field) {
+            let trimmed_bytes = trim_bytes(field);
+            if is_empty(trimmed_bytes, &config.data_config.null_values) {
+                cell_counts[field_index].nulls += 1;
                 continue;
             }
-            let trimmed_bytes = trim_bytes(field);
-            parse_column(
+            let result = parse_column(
                 trimmed_bytes,
                 &config.median_config,
                 &mut column_stats[field_index],
-            )
-            .map_err(|e| CsvColError::ColumnParse(row_index, field_index, e))?;
+            );
+            let error = match result {
+                Ok(())
+                    if !matches!(
+                        column_stats[field_index],
+                        ColumnOption::Uninitialized | ColumnOption::UninitializedWithFilter(_)
+                    ) =>
+                {
+                    continue;
+                }
+                // text before the column is known to be numeric isn't an
+                // error, the column may still turn out to be text
+                Ok(()) => {
+                    held_text[field_index] = true;
+                    None
+                }
+                Err(_) if held_text[field_index] => None,
+                Err(e) => Some(e),
+            };
+            if let Some(e) = error {
+                if let Some(writer) = reject_writer {
+                    reject_reasons.push(format!("{}: {e}", headers[field_index]));
+                    if config.data_config.on_error == OnError::Fail {
+                        reject(&reject_reasons.join("; "))?;
+                        writer.flush()?;
+                    }
+                }
+                match config.data_config.on_error {
+                    OnError::Fail => {
+                        return Err(CsvColError::ColumnParse(row_index, field_index, e));
+                    }
+                    OnError::Skip => cell_counts[field_index].non_numeric += 1,
+                    OnError::Null => cell_counts[field_index].nulls += 1,
+                }
+            } else {
+                // text that may belong to a text column can't fail the file,
+                // otherwise it follows the policy
+                match config.data_config.on_error {
+                    OnError::Fail | OnError::Skip => cell_counts[field_index].non_numeric += 1,
+                    OnError::Null => cell_counts[field_index].nulls += 1,
+                }
+            }
+            let samples = &mut invalid_samples[field_index];
+            if config.data_config.on_error != OnError::Fail
+                && samples.len() < config.data_config.error_samples
+            {
+                samples.push(InvalidValue {
+                    row: row_index,
+                    column: headers[field_index].clone(),
+                    value: String::from_utf8_lossy(field).into_owned(),
+                });
+            }
+        }
+
+        if !reject_reasons.is_empty() {
+            reject(&reject_reasons.join("; "))?;
+            reject_reasons.clear();
         }
     }
 
-    Ok(headers.into_iter().zip(column_stats).collect())
+    if let Some(writer) = reject_writer {
+        writer.flush()?;
+    }
+
+    for ((column, cells), samples) in column_stats
+        .iter_mut()
+        .zip(cell_counts)
+        .zip(invalid_samples)
+    {
+        if let ColumnOption::Number(stats) | ColumnOption::FilteredNumber(stats, _) = column {
+            stats.cells = cells;
+            stats.invalid_samples = samples;
+        }
+    }
+
+    Ok(ParsedReader {
+        columns: headers.into_iter().zip(column_stats).collect(),
+        row_counts,
+        dialect,
+        detected_null_values,
+        warnings,
+    })
 }
 
 #[cfg(test)]
 mod tests {
-    use std::io::{Cursor, Write};
+    use std::io::{BufReader, Cursor, Write};
     use std::path::PathBuf;
 
     use crate::Config as CsvColCinfig;
+    use crate::Number::Int;
+    use crate::parser::Sum;
     use crate::parser::column::ColumnOption::*;
+    use crate::{DialectOverrides, MedianFallback, NullValues, Quantile, SniffConfig};
 
     use super::*;
 
+    /// Compares stats, allowing float noise in derived metrics.
+    fn assert_stats_eq(actual: &Stats, expected: &Stats) {
+        assert_eq!(actual.count, expected.count);
+        assert_eq!(actual.null_count, expected.null_count);
+        assert_eq!(actual.non_numeric_count, expected.non_numeric_count);
+        assert_eq!(actual.min, expected.min);
+        assert_eq!(actual.max, expected.max);
+        assert_eq!(actual.quantiles, expected.quantiles);
+        assert_eq!(actual.invalid_samples, expected.invalid_samples);
+        assert_eq!(actual.precision_loss, expected.precision_loss);
+
+        let metrics = [
+            ("mean", actual.mean, expected.mean),
+            ("median", actual.median, expected.median),
+            ("variance", actual.variance, expected.variance),
+            (
+                "sample_variance",
+                actual.sample_variance,
+                expected.sample_variance,
+            ),
+            ("stddev", actual.stddev, expected.stddev),
+            (
+                "sample_stddev",
+                actual.sample_stddev,
+                expected.sample_stddev,
+            ),
+            ("skewness", actual.skewness, expected.skewness),
+            ("kurtosis", actual.kurtosis, expected.kurtosis),
+        ];
+        for (name, actual, expected) in metrics {
+            let close = match (actual, expected) {
+                (Some(actual), Some(expected)) => {
+                    (actual - expected).abs() <= 1e-9 * expected.abs().max(1.)
+                }
+                (actual, expected) => actual == expected,
+            };
+            assert!(close, "{name}: {actual:?} != {expected:?}");
+        }
+    }
+
     fn build_test_set() -> String {
         "id,value1,value2\n
             1,10,20\n
@@ -177,37 +620,37 @@ mod tests {
         let reader = BufReader::new(cursor);
         let config = CsvColCinfig::default();
 
-        let result = parse_reader(reader, config).unwrap();
+        let mut result = parse_reader(reader, config).unwrap().columns;
 
         assert_eq!(result.len(), 3);
 
-        match result.first() {
+        match result.first_mut() {
             Some((_, Number(stat))) => {
-                assert_eq!(stat.min, Some(1));
-                assert_eq!(stat.max, Some(3));
-                assert_eq!(stat.sum, 6);
+                assert_eq!(stat.min, Some(Int(1)));
+                assert_eq!(stat.max, Some(Int(3)));
+                assert_eq!(stat.sum, Sum::Int(6));
                 assert_eq!(stat.count, 3);
                 assert_eq!(stat.median_approach.calculate().unwrap(), Some(2.));
             }
             _ => panic!("Stat should be initialized"),
         }
 
-        match result.get(1) {
+        match result.get_mut(1) {
             Some((_, Number(stat))) => {
-                assert_eq!(stat.min, Some(10));
-                assert_eq!(stat.max, Some(30));
-                assert_eq!(stat.sum, 65);
+                assert_eq!(stat.min, Some(Int(10)));
+                assert_eq!(stat.max, Some(Int(30)));
+                assert_eq!(stat.sum, Sum::Int(65));
                 assert_eq!(stat.count, 3);
                 assert_eq!(stat.median_approach.calculate().unwrap(), Some(25.));
             }
             _ => panic!("Stat should be initialized"),
         }
 
-        match result.get(2) {
+        match result.get_mut(2) {
             Some((_, Number(stat))) => {
-                assert_eq!(stat.min, Some(20));
-                assert_eq!(stat.max, Some(40));
-                assert_eq!(stat.sum, 95);
+                assert_eq!(stat.min, Some(Int(20)));
+                assert_eq!(stat.max, Some(Int(40)));
+                assert_eq!(stat.sum, Sum::Int(95));
                 assert_eq!(stat.count, 3);
                 assert_eq!(stat.median_approach.calculate().unwrap(), Some(35.));
             }
@@ -232,28 +675,64 @@ mod tests {
         .unwrap();
 
         let id_stats = Stats {
-            min: Some(1),
-            max: Some(3),
+            count: 3,
+            null_count: 0,
+            non_numeric_count: 0,
+            min: Some(Int(1)),
+            max: Some(Int(3)),
             mean: Some(2.),
             median: Some(2.),
+            variance: Some(2. / 3.),
+            sample_variance: Some(1.),
+            stddev: Some(f64::sqrt(2. / 3.)),
+            sample_stddev: Some(f64::sqrt(1.)),
+            skewness: Some(0.),
+            kurtosis: Some(-1.5),
+            quantiles: vec![],
+            invalid_samples: vec![],
+            precision_loss: false,
         };
-        assert_eq!(result.remove("id").unwrap(), id_stats);
+        assert_stats_eq(&result.columns.remove("id").unwrap(), &id_stats);
 
         let value1_stats = Stats {
-            min: Some(10),
-            max: Some(30),
-            mean: Some(21.67),
+            count: 3,
+            null_count: 0,
+            non_numeric_count: 0,
+            min: Some(Int(10)),
+            max: Some(Int(30)),
+            mean: Some(65. / 3.),
             median: Some(25.),
+            variance: Some(650. / 9.),
+            sample_variance: Some(325. / 3.),
+            stddev: Some(f64::sqrt(650. / 9.)),
+            sample_stddev: Some(f64::sqrt(325. / 3.)),
+            skewness: Some(-0.528004979218188),
+            kurtosis: Some(-1.5),
+            quantiles: vec![],
+            invalid_samples: vec![],
+            precision_loss: false,
         };
-        assert_eq!(result.remove("value1").unwrap(), value1_stats);
+        assert_stats_eq(&result.columns.remove("value1").unwrap(), &value1_stats);
 
         let value2_stats = Stats {
-            min: Some(20),
-            max: Some(40),
-            mean: Some(31.67),
+            count: 3,
+            null_count: 0,
+            non_numeric_count: 0,
+            min: Some(Int(20)),
+            max: Some(Int(40)),
+            mean: Some(95. / 3.),
             median: Some(35.),
+            variance: Some(650. / 9.),
+            sample_variance: Some(325. / 3.),
+            stddev: Some(f64::sqrt(650. / 9.)),
+            sample_stddev: Some(f64::sqrt(325. / 3.)),
+            skewness: Some(-0.528004979218188),
+            kurtosis: Some(-1.5),
+            quantiles: vec![],
+            invalid_samples: vec![],
+            precision_loss: false,
         };
-        assert_eq!(result.remove("value2").unwrap(), value2_stats);
+        assert_stats_eq(&result.columns.remove("value2").unwrap(), &value2_stats);
     }
 
     #[test]
@@ -271,22 +750,687 @@ mod tests {
 
         let mut result = parse_file(PathBuf::from(temp_file.path()).as_path(), config).unwrap();
 
-        assert_eq!(result.len(), 2);
+        assert_eq!(result.columns.len(), 2);
 
         let value1_stats = Stats {
-            min: Some(1),
-            max: Some(3),
+            count: 3,
+            null_count: 3,
+            non_numeric_count: 0,
+            min: Some(Int(1)),
+            max: Some(Int(3)),
             mean: Some(2.),
             median: Some(2.),
+            variance: Some(2. / 3.),
+            sample_variance: Some(1.),
+            stddev: Some(f64::sqrt(2. / 3.)),
+            sample_stddev: Some(f64::sqrt(1.)),
+            skewness: Some(0.),
+            kurtosis: Some(-1.5),
+            quantiles: vec![],
+            invalid_samples: vec![],
+            precision_loss: false,
         };
-        assert_eq!(result.remove("value1").unwrap(), value1_stats);
+        assert_stats_eq(&result.columns.remove("value1").unwrap(), &value1_stats);
 
         let value2_stats = Stats {
-            min: Some(10),
-            max: Some(60),
+            count: 6,
+            null_count: 0,
+            non_numeric_count: 0,
+            min: Some(Int(10)),
+            max: Some(Int(60)),
             mean: Some(35.),
             median: Some(35.),
+            variance: Some(875. / 3.),
+            sample_variance: Some(350.),
+            stddev: Some(f64::sqrt(875. / 3.)),
+            sample_stddev: Some(f64::sqrt(350.)),
+            skewness: Some(0.),
+            kurtosis: Some(-1.2685714285714286),
+            quantiles: vec![],
+            invalid_samples: vec![],
+            precision_loss: false,
         };
-        assert_eq!(result.remove("value2").unwrap(), value2_stats);
+        assert_stats_eq(&result.columns.remove("value2").unwrap(), &value2_stats);
+    }
+
+    #[test]
+    fn test_parse_file_quantiles() {
+        let test_set = build_test_set2();
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+
+        temp_file
+            .as_file_mut()
+            .write_all(test_set.as_bytes())
+            .unwrap();
+
+        let mut config = CsvColCinfig::default();
+        config.median_config.quantiles = vec![0.25, 0.75];
+
+        let mut result = parse_file(PathBuf::from(temp_file.path()).as_path(), config).unwrap();
+
+        let quantiles = result.columns.remove("value2").unwrap().quantiles;
+        assert_eq!(
+            quantiles,
+            vec![
+                Quantile {
+                    quantile: 0.25,
+                    value: Some(22.5)
+                },
+                Quantile {
+                    quantile: 0.75,
+                    value: Some(47.5)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_reader_approximate_quantiles() {
+        let values: String = (1..=1000).map(|v| format!("{v}\n")).collect();
+        let cursor = Cursor::new(format!("value\n{values}"));
+        let mut config = CsvColCinfig::default();
+        config.median_config.exact_median = false;
+        config.median_config.quantiles = vec![0.1, 0.99];
+
+        let result = parse_reader(cursor, config).unwrap().columns;
+
+        match result.into_iter().next() {
+            Some((_, Number(stat))) => {
+                let stats: Stats = stat.try_into().unwrap();
+                let p10 = stats.quantiles[0].value.unwrap();
+                let p99 = stats.quantiles[1].value.unwrap();
+                assert!((p10 - 100.).abs() / 100. < 0.02, "p10 = {p10}");
+                assert!((p99 - 990.).abs() / 990. < 0.02, "p99 = {p99}");
+            }
+            _ => panic!("Stat should be initialized"),
+        }
+    }
+
+    #[test]
+    fn test_parse_file_spill_median() {
+        let values: String = (0..5000)
+            .map(|v| format!("{},{}\n", v, (v * 7) % 5000))
+            .collect();
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+
+        temp_file
+            .as_file_mut()
+            .write_all(format!("id,value\n{values}").as_bytes())
+            .unwrap();
+
+        let mut config = CsvColCinfig::default();
+        config.median_config.memory_budget = 1024;
+        config.median_config.fallback = MedianFallback::Spill;
+        config.median_config.quantiles = vec![0.25, 0.75];
+
+        let mut result = parse_file(PathBuf::from(temp_file.path()).as_path(), config).unwrap();
+
+        let stats = result.columns.remove("value").unwrap();
+        assert_eq!(stats.median, Some(2499.5));
+        assert_eq!(stats.quantiles[0].value, Some(1249.75));
+        assert_eq!(stats.quantiles[1].value, Some(3749.25));
+    }
+
+    #[test]
+    fn test_parse_reader_custom_null_values() {
+        let cursor = Cursor::new("id,value\n1,NULL\n2, - \n3,\\N\n4,#n/a\n5,7\n");
+        let mut config = CsvColCinfig::default();
+        config.data_config.null_values = NullValues::new(["NULL", "-", "\\N", "#N/A"], true);
+
+        let result = parse_reader(cursor, config).unwrap().columns;
+
+        match result.get(1) {
+            Some((_, Number(stat))) => {
+                assert_eq!(stat.count, 1);
+                assert_eq!(stat.cells.nulls, 4);
+            }
+            _ => panic!("Stat should be initialized"),
+        }
+    }
+
+    fn build_test_set_with_errors() -> String {
+        "id,value\n1,10\n2,abc\n3,20\n4,x1\n5,30\n".to_string()
+    }
+
+    #[test]
+    fn test_parse_reader_on_error_fail() {
+        let cursor = Cursor::new(build_test_set_with_errors());
+        let config = CsvColCinfig::default();
+
+        let result = parse_reader(cursor, config);
+
+        assert!(matches!(result, Err(CsvColError::ColumnParse(1, 1, _))));
+    }
+
+    #[test]
+    fn test_parse_reader_on_error_skip() {
+        let cursor = Cursor::new(build_test_set_with_errors());
+        let mut config = CsvColCinfig::default();
+        config.data_config.on_error = OnError::Skip;
+        config.data_config.error_samples = 1;
+
+        let result = parse_reader(cursor, config).unwrap().columns;
+
+        match result.get(1) {
+            Some((_, Number(stat))) => {
+                assert_eq!(stat.count, 3);
+                assert_eq!(stat.cells.non_numeric, 2);
+                assert_eq!(stat.cells.nulls, 0);
+                assert_eq!(
+                    stat.invalid_samples,
+                    vec![InvalidValue {
+                        row: 1,
+                        column: "value".to_string(),
+                        value: "abc".to_string(),
+                    }]
+                );
+            }
+            _ => panic!("Stat should be initialized"),
+        }
+    }
+
+    #[test]
+    fn test_parse_reader_on_error_skip_before_classified() {
+        let cursor = Cursor::new("id,value\n1,abc\n2,10\n3,x\n");
+        let mut config = CsvColCinfig::default();
+        config.data_config.on_error = OnError::Skip;
+        config.data_config.error_samples = 5;
+
+        let result = parse_reader(cursor, config).unwrap().columns;
+
+        match result.get(1) {
+            Some((_, Number(stat))) => {
+                assert_eq!(stat.count, 1);
+                assert_eq!(stat.cells.non_numeric, 2);
+                let samples: Vec<_> = stat
+                    .invalid_samples
+                    .iter()
+                    .map(|sample| (sample.row, sample.value.as_str()))
+                    .collect();
+                assert_eq!(samples, [(0, "abc"), (2, "x")]);
+            }
+            _ => panic!("Stat should be initialized"),
+        }
+    }
+
+    #[test]
+    fn test_parse_reader_on_error_null_before_classified() {
+        for (on_error, nulls, non_numeric, samples) in
+            [(OnError::Null, 2, 0, 2), (OnError::Fail, 0, 2, 0)]
+        {
+            let cursor = Cursor::new("v\nabc\n1\nxyz\n");
+            let mut config = CsvColCinfig::default();
+            config.data_config.on_error = on_error;
+            config.data_config.error_samples = 5;
+
+            let result = parse_reader(cursor, config).unwrap().columns;
+
+            match result.first() {
+                Some((_, Number(stat))) => {
+                    assert_eq!(stat.count, 1);
+                    assert_eq!(stat.cells.nulls, nulls, "{on_error:?}");
+                    assert_eq!(stat.cells.non_numeric, non_numeric, "{on_error:?}");
+                    assert_eq!(stat.invalid_samples.len(), samples, "{on_error:?}");
+                }
+                _ => panic!("Stat should be initialized"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_reader_on_error_null() {
+        let cursor = Cursor::new(build_test_set_with_errors());
+        let mut config = CsvColCinfig::default();
+        config.data_config.on_error = OnError::Null;
+
+        let result = parse_reader(cursor, config).unwrap().columns;
+
+        match result.get(1) {
+            Some((_, Number(stat))) => {
+                assert_eq!(stat.count, 3);
+                assert_eq!(stat.cells.non_numeric, 0);
+                assert_eq!(stat.cells.nulls, 2);
+                assert!(stat.invalid_samples.is_empty());
+            }
+            _ => panic!("Stat should be initialized"),
+        }
+    }
+
+    #[test]
+    fn test_parse_file_reject_file() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        temp_file
+            .as_file_mut()
+            .write_all("id,value,other\n1,10,1\n2,abc,2\n3,20,x\n4,30,y\n".as_bytes())
+            .unwrap();
+        let reject_file = tempfile::NamedTempFile::new().unwrap();
+
+        let mut config = CsvColCinfig::default();
+        config.data_config.on_error = OnError::Skip;
+        config.open_reject_file(reject_file.path()).unwrap();
+
+        let mut result = parse_file(temp_file.path(), config).unwrap();
+        assert_eq!(result.columns.remove("value").unwrap().non_numeric_count, 1);
+
+        let source = temp_file.path().to_string_lossy();
+        let rejected = std::fs::read_to_string(reject_file.path()).unwrap();
+        let reason = "Can't parse number: lexical parse error: 'invalid digit found' at index 0";
+        assert_eq!(
+            rejected,
+            format!(
+                "source,line,reason,record\n\
+                 {source},3,value: {reason},\"2,abc,2\"\n\
+                 {source},4,other: {reason},\"3,20,x\"\n\
+                 {source},5,other: {reason},\"4,30,y\"\n"
+            )
+        );
+    }
+
+    fn read_rejected(path: &Path) -> Vec<Vec<String>> {
+        csv::Reader::from_path(path)
+            .unwrap()
+            .records()
+            .map(|record| record.unwrap().iter().map(str::to_owned).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_reader_reject_raw_record() {
+        let cursor = Cursor::new(
+            "id,value\r\n1,10\r\n\r\n# note\r\n2,\"a\"\"b\r\nc\"\r\n3,20\r\n4,  x \r\n",
+        );
+        let reject_file = tempfile::NamedTempFile::new().unwrap();
+        let mut config = CsvColCinfig::default();
+        config.data_config.on_error = OnError::Skip;
+        config.data_config.dialect.comment = Some(b'#');
+        config.open_reject_file(reject_file.path()).unwrap();
+
+        let result = parse_reader(cursor, config).unwrap();
+        assert_eq!(result.row_counts.rows, 4);
+
+        let rejected = read_rejected(reject_file.path());
+        let lines_and_records: Vec<_> = rejected
+            .iter()
+            .map(|row| (row[1].as_str(), row[3].as_str()))
+            .collect();
+        assert_eq!(
+            lines_and_records,
+            [("5", "2,\"a\"\"b\r\nc\""), ("8", "4,  x ")]
+        );
+        assert!(rejected.iter().all(|row| row[2].starts_with("value: ")));
+    }
+
+    struct Truncated;
+
+    impl Read for Truncated {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("unexpected end of stream"))
+        }
+    }
+
+    #[test]
+    fn test_parse_reader_reject_csv_error() {
+        let input = || Cursor::new("id,value\n1,10\n2,20\n3,3").chain(Truncated);
+        let reject_file = tempfile::NamedTempFile::new().unwrap();
+        let mut config = CsvColCinfig::default();
+        config.open_reject_file(reject_file.path()).unwrap();
+        assert!(parse_reader(input(), config.clone()).is_err());
+
+        config.data_config.on_error = OnError::Skip;
+        let result = parse_reader(input(), config).unwrap();
+        assert_eq!(result.row_counts.rows, 2);
+        assert_eq!(
+            result.warnings,
+            ["Stopped reading at line 4: unexpected end of stream"]
+        );
+        match &result.columns[1].1 {
+            Number(stats) => assert_eq!(stats.count, 2),
+            _ => panic!("Stat should be initialized"),
+        }
+
+        let rejected = read_rejected(reject_file.path());
+        assert_eq!(rejected, [["", "4", "unexpected end of stream", "3,3"]]);
+    }
+
+    fn parse_ragged(ragged: RaggedRows) -> Result<ParsedReader> {
+        let cursor = Cursor::new("id,a,b\n1,10,20\n2,30\n3,40,50,60\n4,50,70\n");
+        let mut config = CsvColCinfig::default();
+        config.data_config.ragged = ragged;
+        parse_reader(cursor, config)
+    }
+
+    fn counts(parsed: &ParsedReader, index: usize) -> (usize, usize) {
+        match &parsed.columns[index] {
+            (_, Number(stat)) => (stat.count, stat.cells.nulls),
+            _ => panic!("Stat should be initialized"),
+        }
+    }
+
+    #[test]
+    fn test_parse_reader_ragged_error() {
+        let result = parse_ragged(RaggedRows::Error);
+        assert!(matches!(result, Err(CsvColError::RaggedRow(1, 2, 3))));
+    }
+
+    #[test]
+    fn test_parse_reader_ragged_pad() {
+        let parsed = parse_ragged(RaggedRows::Pad).unwrap();
+        assert_eq!(
+            parsed.row_counts,
+            RowCounts {
+                rows: 4,
+                ragged_rows: 2,
+                filtered_rows: 0,
+            }
+        );
+        assert_eq!(counts(&parsed, 1), (4, 0));
+        assert_eq!(counts(&parsed, 2), (3, 1));
+    }
+
+    #[test]
+    fn test_parse_reader_ragged_truncate() {
+        let parsed = parse_ragged(RaggedRows::Truncate).unwrap();
+        assert_eq!(parsed.row_counts.ragged_rows, 2);
+        assert_eq!(counts(&parsed, 1), (4, 0));
+        assert_eq!(counts(&parsed, 2), (3, 0));
+    }
+
+    #[test]
+    fn test_parse_reader_ragged_skip() {
+        let parsed = parse_ragged(RaggedRows::Skip).unwrap();
+        assert_eq!(parsed.row_counts.rows, 4);
+        assert_eq!(counts(&parsed, 1), (2, 0));
+        assert_eq!(counts(&parsed, 2), (2, 0));
+    }
+
+    #[test]
+    fn test_parse_stream() {
+        let values: String = (0..2000).map(|v| format!("{v}\n")).collect();
+        let cursor = Cursor::new(format!("value\n{values}"));
+        let mut config = CsvColCinfig::default();
+        config.median_config.memory_budget = 1024;
+        config.median_config.fallback = MedianFallback::Spill;
+
+        let mut result = parse_stream(cursor, config).unwrap();
+
+        assert_eq!(result.row_counts.rows, 2000);
+        assert_eq!(result.columns.remove("value").unwrap().median, Some(999.5));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_parse_file_gzip() {
+        let mut temp_file = tempfile::Builder::new().suffix(".csv").tempfile().unwrap();
+        let mut encoder =
+            flate2::write::GzEncoder::new(temp_file.as_file_mut(), Default::default());
+        encoder.write_all(build_test_set().as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let mut result = parse_file(temp_file.path(), CsvColCinfig::default()).unwrap();
+
+        assert_eq!(result.row_counts.rows, 3);
+        assert_eq!(result.columns.remove("value1").unwrap().median, Some(25.));
+    }
+
+    #[test]
+    fn test_parse_reader_dialect() {
+        let cursor = Cursor::new("# export\nid;a;b\n1;10;'x;y'\n# note\n2;20;'z'\n");
+        let mut config = CsvColCinfig::default();
+        config.data_config.dialect = Dialect {
+            delimiter: b';',
+            quote: b'\'',
+            comment: Some(b'#'),
+            ..Dialect::default()
+        };
+
+        let parsed = parse_reader(cursor, config).unwrap();
+
+        assert_eq!(parsed.row_counts.ragged_rows, 0);
+        assert_eq!(counts(&parsed, 1), (2, 0));
+        assert!(matches!(parsed.columns[2], (_, Uninitialized)));
+    }
+
+    #[test]
+    fn test_parse_reader_non_numeric_before_classified() {
+        let cursor = Cursor::new("id,value,name\n1,abc,x\n2,,y\n3,10,z\n4,20,w\n");
+        let config = CsvColCinfig::default();
+
+        let parsed = parse_reader(cursor, config).unwrap();
+
+        assert_eq!(counts(&parsed, 1), (2, 1));
+        match &parsed.columns[1] {
+            (_, Number(stat)) => assert_eq!(stat.cells.non_numeric, 1),
+            _ => panic!("Stat should be initialized"),
+        }
+        assert!(matches!(parsed.columns[2], (_, Uninitialized)));
+    }
+
+    #[test]
+    fn test_parse_reader_text_column_with_number() {
+        let cursor = Cursor::new("id,name,value\n1,foo,10\n2,123,20\n3,bar,30\n");
+        let config = CsvColCinfig::default();
+
+        let parsed = parse_reader(cursor, config).unwrap();
+
+        match &parsed.columns[1] {
+            (_, Number(stat)) => {
+                assert_eq!(stat.count, 1);
+                assert_eq!(stat.cells.non_numeric, 2);
+            }
+            _ => panic!("Stat should be initialized"),
+        }
+        assert_eq!(counts(&parsed, 2), (3, 0));
+    }
+
+    #[test]
+    fn test_parse_reader_terminator() {
+        let cursor = Cursor::new("id,a|1,10|2,20|");
+        let mut config = CsvColCinfig::default();
+        config.data_config.dialect.terminator = Terminator::Byte(b'|');
+
+        let parsed = parse_reader(cursor, config).unwrap();
+
+        assert_eq!(parsed.row_counts.rows, 2);
+        assert_eq!(counts(&parsed, 1), (2, 0));
+    }
+
+    #[test]
+    fn test_parse_reader_no_header() {
+        let cursor = Cursor::new("1,10,5\n2,20,6\n3,30,7\n");
+        let mut config = CsvColCinfig::default();
+        config.data_config.dialect.has_header = false;
+        config.data_config.ignore_columns = vec!["#0".to_string()];
+        config.data_config.filter = Some("#1 > 15".parse().unwrap());
+        config.data_config.filter_mode = FilterMode::Column;
+
+        let parsed = parse_reader(cursor, config).unwrap();
+
+        assert_eq!(parsed.row_counts.rows, 3);
+        let names: Vec<_> = parsed
+            .columns
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(names, ["col_0", "col_1", "col_2"]);
+        assert!(matches!(parsed.columns[0], (_, Ignored)));
+        match &parsed.columns[1] {
+            (_, FilteredNumber(stat, _)) => assert_eq!(stat.count, 2),
+            _ => panic!("Stat should be filtered"),
+        }
+        assert_eq!(counts(&parsed, 2), (3, 0));
+    }
+
+    #[test]
+    fn test_parse_reader_row_filter() {
+        let cursor = Cursor::new("status,a,b\npaid,10,1\nopen,20,\npaid,30,null\n,40,4\n");
+        let mut config = CsvColCinfig::default();
+        config.data_config.filter = Some("status == 'paid' && a >= 10".parse().unwrap());
+
+        let parsed = parse_reader(cursor, config).unwrap();
+
+        assert_eq!(parsed.row_counts.rows, 4);
+        assert_eq!(parsed.row_counts.filtered_rows, 2);
+        assert_eq!(counts(&parsed, 1), (2, 0));
+        assert_eq!(counts(&parsed, 2), (1, 1));
+    }
+
+    #[test]
+    fn test_parse_reader_row_filter_ragged_pad() {
+        let cursor = Cursor::new("id,a,b\n1,10,20\n2,30\n3,40,50,60\n4,50,70\n");
+        let mut config = CsvColCinfig::default();
+        config.data_config.ragged = RaggedRows::Pad;
+        config.data_config.filter = Some("a > 20".parse().unwrap());
+
+        let parsed = parse_reader(cursor, config).unwrap();
+
+        assert_eq!(parsed.row_counts.ragged_rows, 2);
+        assert_eq!(parsed.row_counts.filtered_rows, 1);
+        assert_eq!(counts(&parsed, 1), (3, 0));
+        assert_eq!(counts(&parsed, 2), (2, 1));
+    }
+
+    #[test]
+    fn test_parse_reader_row_filter_columns() {
+        let cursor = Cursor::new("start_ts,end_ts,price,cost\n1,5,10,9\n5,1,13,10\n2,2,13,10\n");
+        let mut config = CsvColCinfig::default();
+        config.data_config.filter =
+            Some("end_ts >= start_ts && price > cost * 1.2".parse().unwrap());
+
+        let parsed = parse_reader(cursor, config).unwrap();
+
+        assert_eq!(parsed.row_counts.filtered_rows, 2);
+        assert_eq!(counts(&parsed, 2), (1, 0));
+        match &parsed.columns[2] {
+            (_, Number(stat)) => assert_eq!(stat.min, Some(crate::Number::Int(13))),
+            _ => panic!("Stat should be initialized"),
+        }
+    }
+
+    #[test]
+    fn test_parse_reader_row_filter_unknown_column() {
+        for filter_mode in [FilterMode::Row, FilterMode::Column] {
+            let cursor = Cursor::new("id,a\n1,10\n");
+            let mut config = CsvColCinfig::default();
+            config.data_config.filter = Some("a > 1 && c > 1".parse().unwrap());
+            config.data_config.filter_mode = filter_mode;
+
+            let result = parse_reader(cursor, config);
+
+            assert!(
+                matches!(&result, Err(CsvColError::UnknownFilterColumn(name)) if name == "c"),
+                "{filter_mode:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_reader_column_filter_cross_column() {
+        for filter in ["a > b", "a > 1 && !(a + b IS NULL)", "a BETWEEN 1 AND #2"] {
+            let cursor = Cursor::new("id,a,b\n1,10,5\n");
+            let mut config = CsvColCinfig::default();
+            config.data_config.filter = Some(filter.parse().unwrap());
+            config.data_config.filter_mode = FilterMode::Column;
+
+            let result = parse_reader(cursor, config);
+
+            assert!(
+                matches!(&result, Err(CsvColError::CrossColumnFilter(first, second)) if first == "a" && second == "b"),
+                "{filter}"
+            );
+        }
+
+        for filter in ["a > 1 || b < 2", "a + #1 > 1"] {
+            let cursor = Cursor::new("id,a,b\n1,10,5\n");
+            let mut config = CsvColCinfig::default();
+            config.data_config.filter = Some(filter.parse().unwrap());
+            config.data_config.filter_mode = FilterMode::Column;
+
+            assert!(parse_reader(cursor, config).is_ok(), "{filter}");
+        }
+    }
+
+    #[test]
+    fn test_parse_reader_names() {
+        let cursor = Cursor::new("id,a,b\n1,10,5\n");
+        let mut config = CsvColCinfig::default();
+        config.data_config.names = vec!["key".to_string(), "x".to_string()];
+
+        let parsed = parse_reader(cursor, config).unwrap();
+
+        assert_eq!(parsed.row_counts.rows, 1);
+        let names: Vec<_> = parsed
+            .columns
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(names, ["key", "x", "b"]);
+    }
+
+    #[test]
+    fn test_parse_file_duplicate_headers() {
+        let mut temp_file = tempfile::NamedTempFile::new().unwrap();
+        temp_file
+            .as_file_mut()
+            .write_all("value,b,value,value#2\n1,2,3,4\n".as_bytes())
+            .unwrap();
+
+        let result = parse_file(temp_file.path(), CsvColCinfig::default()).unwrap();
+
+        let names: Vec<_> = result.columns.iter().map(|(name, _)| name).collect();
+        assert_eq!(names, ["value", "b", "value#3", "value#2"]);
+        assert_eq!(result.columns.get("value#3").unwrap().min, Some(Int(3)));
+        assert_eq!(
+            result.warnings,
+            ["duplicate column 'value' at index 2 renamed to 'value#3'"]
+        );
+    }
+
+    fn sniff_config(overrides: DialectOverrides) -> CsvColCinfig {
+        let mut config = CsvColCinfig::default();
+        config.data_config.sniff = Some(SniffConfig {
+            sample_size: 16,
+            overrides,
+        });
+        config
+    }
+
+    #[test]
+    fn test_parse_reader_sniff() {
+        let cursor = Cursor::new("id;a\n1;10\n2;-\n3;30\n4;40\n5;50\n");
+        let config = sniff_config(DialectOverrides::default());
+
+        let parsed = parse_reader(cursor, config).unwrap();
+
+        assert_eq!(parsed.dialect.delimiter, b';');
+        assert_eq!(parsed.detected_null_values, vec!["-"]);
+        assert_eq!(parsed.row_counts.rows, 5);
+        assert_eq!(counts(&parsed, 1), (4, 1));
+    }
+
+    #[test]
+    fn test_parse_reader_sniff_no_header() {
+        let cursor = Cursor::new("1,10\n2,20\n3,30\n");
+        let config = sniff_config(DialectOverrides::default());
+
+        let parsed = parse_reader(cursor, config).unwrap();
+
+        assert!(!parsed.dialect.has_header);
+        assert_eq!(parsed.row_counts.rows, 3);
+        assert_eq!(parsed.columns[1].0, "col_1");
+        assert_eq!(counts(&parsed, 1), (3, 0));
+    }
+
+    #[test]
+    fn test_parse_reader_sniff_override() {
+        let cursor = Cursor::new("id;a\n1;10\n2;20\n");
+        let config = sniff_config(DialectOverrides {
+            delimiter: Some(b','),
+            ..DialectOverrides::default()
+        });
+
+        let parsed = parse_reader(cursor, config).unwrap();
+
+        assert_eq!(parsed.dialect.delimiter, b',');
+        assert_eq!(parsed.columns.len(), 1);
     }
 }
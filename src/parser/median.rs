@@ -1,94 +1,229 @@
 use sketches_ddsketch::{Config as DDConfig, DDSketch};
-use std::{cmp::Reverse, collections::BinaryHeap};
+use std::{cmp::Ordering, collections::BTreeMap, convert::Infallible};
 
-use crate::{CsvColError, MedianConfig, Result};
+use crate::{CsvColError, MedianConfig, MedianFallback, Result, parser::spill::SpillingQuantiles};
 
+/// `f64` wrapper with a total order so it can be used as a map or heap key.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(in crate::parser) struct OrderedValue(pub f64);
+
+impl Eq for OrderedValue {}
+
+impl PartialOrd for OrderedValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrderedValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// Closest zero-based ranks of quantile `q` among `count` values, and the
+/// weight used to linearly interpolate between them.
+pub(in crate::parser) fn quantile_ranks(q: f64, count: u64) -> (u64, u64, f64) {
+    let position = q * (count - 1) as f64;
+    let lower = position.floor();
+    (lower as u64, position.ceil() as u64, position - lower)
+}
+
+/// Every `q` in `quantiles` among `count` values, linearly interpolated
+/// between the closest ranks, so `0.5` is the usual median.
+///
+/// `values_at` is given the needed zero-based ranks, sorted and without
+/// duplicates, and returns the value at each of them, so a single ordered
+/// pass over the values answers all quantiles.
+pub(in crate::parser) fn interpolate_quantiles<E>(
+    quantiles: &[f64],
+    count: u64,
+    values_at: impl FnOnce(&[u64]) -> std::result::Result<Vec<f64>, E>,
+) -> std::result::Result<Vec<Option<f64>>, E> {
+    if count == 0 {
+        return Ok(vec![None; quantiles.len()]);
+    }
+
+    let positions: Vec<_> = quantiles
+        .iter()
+        .map(|&q| quantile_ranks(q, count))
+        .collect();
+    let mut ranks: Vec<u64> = positions
+        .iter()
+        .flat_map(|&(lower, upper, _)| [lower, upper])
+        .collect();
+    ranks.sort_unstable();
+    ranks.dedup();
+
+    let values = values_at(&ranks)?;
+    let value_at = |rank: u64| {
+        ranks
+            .binary_search(&rank)
+            .ok()
+            .and_then(|index| values.get(index).copied())
+    };
+
+    Ok(positions
+        .into_iter()
+        .map(|(lower, upper, weight)| {
+            let lower = value_at(lower)?;
+            let upper = value_at(upper)?;
+            Some(lower + (upper - lower) * weight)
+        })
+        .collect())
+}
+
+/// All column values, kept unsorted until quantiles are queried.
+///
+/// Each value takes 8 bytes, there is no per-entry overhead to account for
+/// when files are checked against the memory budget.
 #[derive(Default)]
-pub struct MedianHeap {
-    top: BinaryHeap<Reverse<i64>>,
-    bottom: BinaryHeap<i64>,
+pub struct ExactQuantiles {
+    values: Vec<f64>,
 }
 
-impl MedianHeap {
+impl ExactQuantiles {
     fn new() -> Self {
-        Self {
-            top: BinaryHeap::new(),
-            bottom: BinaryHeap::new(),
-        }
+        Self { values: Vec::new() }
     }
 
-    fn add(&mut self, value: i64) {
-        if self
-            .bottom
-            .peek()
-            .is_none_or(|&bottom_max| bottom_max >= value)
-        {
-            self.bottom.push(value);
-        } else {
-            self.top.push(Reverse(value));
-        }
+    fn add(&mut self, value: f64) {
+        self.values.push(value);
+    }
 
-        if self.bottom.len() > self.top.len() + 1 {
-            let value = self.bottom.pop().unwrap();
-            self.top.push(Reverse(value));
-        }
-        if self.top.len() > self.bottom.len() + 1 {
-            let value = self.top.pop().unwrap().0;
-            self.bottom.push(value);
+    /// Values at the sorted zero-based `ranks`, each selected from the part
+    /// of the values left after the previous one.
+    fn values_at(&mut self, ranks: &[u64]) -> Vec<f64> {
+        let mut values = Vec::with_capacity(ranks.len());
+        let mut start = 0;
+        for &rank in ranks {
+            let rank = rank as usize;
+            let (_, value, _) =
+                self.values[start..].select_nth_unstable_by(rank - start, f64::total_cmp);
+            values.push(*value);
+            start = rank + 1;
         }
+        values
+    }
 
-        if let (Some(&bottom), Some(&Reverse(top))) = (self.bottom.peek(), self.top.peek())
-            && bottom > top
-        {
-            let bottom = self.bottom.pop().unwrap();
-            let top = self.top.pop().unwrap().0;
+    fn quantiles(&mut self, quantiles: &[f64]) -> Vec<Option<f64>> {
+        let count = self.values.len() as u64;
+        let Ok(values) = interpolate_quantiles(quantiles, count, |ranks| {
+            Ok::<_, Infallible>(self.values_at(ranks))
+        });
+        values
+    }
+}
 
-            self.bottom.push(top);
-            self.top.push(Reverse(bottom));
+/// Ordered multiset of column values answering exact rank queries.
+///
+/// Repeated values share a single entry, so columns with few distinct values
+/// stay small, and the number of entries bounds the memory used.
+#[derive(Default)]
+pub struct DistinctQuantiles {
+    values: BTreeMap<OrderedValue, u64>,
+    count: u64,
+}
+
+impl DistinctQuantiles {
+    fn new() -> Self {
+        Self {
+            values: BTreeMap::new(),
+            count: 0,
         }
     }
 
-    fn median(&self) -> Option<f64> {
-        if self.bottom.is_empty() && self.top.is_empty() {
-            return None;
-        }
+    fn add(&mut self, value: f64) {
+        *self.values.entry(OrderedValue(value)).or_default() += 1;
+        self.count += 1;
+    }
 
-        if self.top.len() > self.bottom.len() {
-            Some(self.top.peek().unwrap().0 as f64)
-        } else if self.bottom.len() > self.top.len() {
-            Some(*self.bottom.peek().unwrap() as f64)
-        } else {
-            let top = self.top.peek().unwrap().0 as f64;
-            let bottom = *self.bottom.peek().unwrap() as f64;
-            Some((top + bottom) / 2.)
+    /// Number of distinct values kept.
+    fn entries(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Values at the sorted zero-based `ranks`, in one pass over the map.
+    fn values_at(&self, ranks: &[u64]) -> Vec<f64> {
+        let mut values = Vec::with_capacity(ranks.len());
+        let mut wanted = ranks.iter().peekable();
+        let mut seen = 0;
+        for (value, count) in &self.values {
+            seen += count;
+            while wanted.next_if(|&&rank| rank < seen).is_some() {
+                values.push(value.0);
+            }
+            if wanted.peek().is_none() {
+                break;
+            }
         }
+        values
+    }
+
+    fn quantiles(&self, quantiles: &[f64]) -> Vec<Option<f64>> {
+        let Ok(values) = interpolate_quantiles(quantiles, self.count, |ranks| {
+            Ok::<_, Infallible>(self.values_at(ranks))
+        });
+        values
     }
 }
 
+/// Smallest number of values buffered in memory before spilling a run.
+const MIN_SPILL_CAPACITY: usize = 1024;
+
+/// Rough memory used by a distinct value in [`DistinctQuantiles`], including
+/// the tree overhead.
+const DISTINCT_ENTRY_SIZE: usize = 32;
+
 pub enum MedianSettings {
     Exact,
     Approximate(Option<u32>),
+    /// Exact, spilling runs of this many values to disk.
+    External(usize),
+    /// Exact while at most `max_entries` distinct values are kept, then
+    /// switches to `fallback`.
+    Bounded {
+        max_entries: usize,
+        fallback: Box<MedianSettings>,
+    },
 }
 
 impl From<&MedianConfig> for MedianSettings {
     fn from(config: &MedianConfig) -> Self {
-        if config.exact_median {
+        let fallback = match config.fallback {
+            MedianFallback::Approximate => MedianSettings::Approximate(config.buckets),
+            MedianFallback::Spill => MedianSettings::External(
+                (config.memory_budget / size_of::<f64>()).max(MIN_SPILL_CAPACITY),
+            ),
+        };
+        if config.streaming {
+            MedianSettings::Bounded {
+                max_entries: config.memory_budget / DISTINCT_ENTRY_SIZE,
+                fallback: Box::new(fallback),
+            }
+        } else if config.exact_median {
             MedianSettings::Exact
         } else {
-            MedianSettings::Approximate(config.buckets)
+            fallback
         }
     }
 }
 
 pub enum Median {
-    Exact(MedianHeap),
+    Exact(ExactQuantiles),
     Approximate(DDSketch),
+    External(SpillingQuantiles),
+    Bounded {
+        values: DistinctQuantiles,
+        max_entries: usize,
+        fallback: Box<MedianSettings>,
+    },
 }
 
 impl Median {
     pub fn new(config: MedianSettings) -> Self {
         match config {
-            MedianSettings::Exact => Self::Exact(MedianHeap::new()),
+            MedianSettings::Exact => Self::Exact(ExactQuantiles::new()),
             MedianSettings::Approximate(bins) => {
                 let mut ddcondig = DDConfig::default();
                 if let Some(value) = bins {
@@ -96,51 +231,140 @@ impl Median {
                 };
                 Self::Approximate(DDSketch::new(ddcondig))
             }
+            MedianSettings::External(capacity) => Self::External(SpillingQuantiles::new(capacity)),
+            MedianSettings::Bounded {
+                max_entries,
+                fallback,
+            } => Self::Bounded {
+                values: DistinctQuantiles::new(),
+                max_entries,
+                fallback,
+            },
         }
     }
 
-    pub fn add(&mut self, value: i64) {
+    pub fn add(&mut self, value: f64) {
         match self {
-            Self::Exact(heaps) => heaps.add(value),
-            Self::Approximate(ddsketch) => ddsketch.add(value as f64),
+            Self::Exact(values) => values.add(value),
+            Self::Approximate(ddsketch) => ddsketch.add(value),
+            Self::External(values) => values.add(value),
+            Self::Bounded {
+                values,
+                max_entries,
+                ..
+            } => {
+                values.add(value);
+                if values.entries() > *max_entries {
+                    self.switch_to_fallback();
+                }
+            }
         }
     }
 
-    pub fn calculate(&self) -> Result<Option<f64>> {
+    /// Replaces bounded exact values with the fallback strategy, replaying
+    /// everything collected so far.
+    fn switch_to_fallback(&mut self) {
+        let Self::Bounded {
+            values, fallback, ..
+        } = std::mem::replace(self, Self::Exact(ExactQuantiles::new()))
+        else {
+            return;
+        };
+
+        let mut median = Median::new(*fallback);
+        for (value, count) in values.values {
+            for _ in 0..count {
+                median.add(value.0);
+            }
+        }
+        *self = median;
+    }
+
+    #[cfg(test)]
+    pub fn calculate(&mut self) -> Result<Option<f64>> {
+        Ok(self.quantiles(&[0.5])?[0])
+    }
+
+    /// Values of every `q` in `quantiles`, in the same order. Exact values
+    /// are reordered in place to find them.
+    pub fn quantiles(&mut self, quantiles: &[f64]) -> Result<Vec<Option<f64>>> {
         match self {
-            Self::Approximate(ddsketch) => ddsketch.quantile(0.5).map_err(CsvColError::DDSketch),
-            Self::Exact(heaps) => Ok(heaps.median()),
+            Self::Approximate(ddsketch) => quantiles
+                .iter()
+                .map(|&q| ddsketch.quantile(q).map_err(CsvColError::DDSketch))
+                .collect(),
+            Self::Exact(values) => Ok(values.quantiles(quantiles)),
+            Self::Bounded { values, .. } => Ok(values.quantiles(quantiles)),
+            Self::External(values) => values.quantiles(quantiles).map_err(CsvColError::Spill),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::parser::median::MedianHeap;
+    use crate::parser::median::{DistinctQuantiles, ExactQuantiles};
+
+    /// Quantiles of `test_set` from both exact strategies, checked to agree.
+    fn quantiles(test_set: &[f64], quantiles: &[f64]) -> Vec<Option<f64>> {
+        let mut exact = ExactQuantiles::new();
+        let mut distinct = DistinctQuantiles::new();
+        for item in test_set {
+            exact.add(*item);
+            distinct.add(*item);
+        }
+        let result = exact.quantiles(quantiles);
+        assert_eq!(distinct.quantiles(quantiles), result);
+        result
+    }
 
     #[test]
-    fn test_median_heap_empty() {
-        let heap = MedianHeap::new();
-        assert_eq!(heap.median(), None);
+    fn test_exact_median_empty() {
+        assert_eq!(quantiles(&[], &[0.5, 0.9]), [None, None]);
     }
 
     #[test]
-    fn test_median_heap_odd() {
-        let test_set = vec![1, 3, 5, 8, 9, 6, 3];
-        let mut heap = MedianHeap::new();
-        for item in test_set {
-            heap.add(item);
-        }
-        assert_eq!(heap.median().unwrap(), 5.);
+    fn test_exact_median_odd() {
+        assert_eq!(quantiles(&[1., 3., 5., 8., 9., 6., 3.], &[0.5]), [Some(5.)]);
     }
 
     #[test]
-    fn test_median_heap_even() {
-        let test_set = vec![12, 6, 1, 2, 3, 8];
-        let mut heap = MedianHeap::new();
-        for item in test_set {
-            heap.add(item);
+    fn test_exact_median_even() {
+        assert_eq!(quantiles(&[12., 6., 1., 2., 3., 8.], &[0.5]), [Some(4.5)]);
+    }
+
+    #[test]
+    fn test_exact_median_float() {
+        assert_eq!(quantiles(&[0.5, -1.25, 3.75, 2.], &[0.5]), [Some(1.25)]);
+    }
+
+    #[test]
+    fn test_exact_quantiles() {
+        // sorted: 1 2 3 3 3 4 5 7 10
+        assert_eq!(
+            quantiles(
+                &[5., 1., 4., 2., 3., 3., 3., 10., 7.],
+                &[0.9375, 0., 0.75, 0.25, 1., 0.5]
+            ),
+            [Some(8.5), Some(1.), Some(5.), Some(3.), Some(10.), Some(3.)]
+        );
+    }
+
+    #[test]
+    fn test_bounded_median_switches_to_fallback() {
+        use crate::parser::median::{Median, MedianSettings};
+
+        let mut median = Median::new(MedianSettings::Bounded {
+            max_entries: 3,
+            fallback: Box::new(MedianSettings::External(2)),
+        });
+        for value in [4., 4., 1., 2.] {
+            median.add(value);
         }
-        assert_eq!(heap.median().unwrap(), 4.5);
+        assert!(matches!(median, Median::Bounded { .. }));
+
+        median.add(3.);
+        assert!(matches!(median, Median::External(_)));
+        median.add(5.);
+        assert_eq!(median.calculate().unwrap(), Some(3.5));
     }
 }
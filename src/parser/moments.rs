@@ -0,0 +1,124 @@
+/// Running mean and central moments updated in a single pass.
+///
+/// Uses Welford's method extended to the third and fourth moments. Unlike
+/// `sum / count` the mean never overflows and stays stable on long inputs,
+/// so it is used whenever the running [`Sum`](super::Sum) is not finite.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Moments {
+    count: u64,
+    mean: f64,
+    m2: f64,
+    m3: f64,
+    m4: f64,
+}
+
+impl Moments {
+    pub fn add(&mut self, value: f64) {
+        let previous_count = self.count as f64;
+        self.count += 1;
+        let count = self.count as f64;
+
+        let delta = value - self.mean;
+        let delta_n = delta / count;
+        let delta_n2 = delta_n * delta_n;
+        let term = delta * delta_n * previous_count;
+
+        self.mean += delta_n;
+        self.m4 += term * delta_n2 * (count * count - 3. * count + 3.) + 6. * delta_n2 * self.m2
+            - 4. * delta_n * self.m3;
+        self.m3 += term * delta_n * (count - 2.) - 3. * delta_n * self.m2;
+        self.m2 += term;
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then_some(self.mean)
+    }
+
+    /// Population variance.
+    pub fn variance(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.m2 / self.count as f64)
+    }
+
+    /// Sample variance with Bessel's correction.
+    pub fn sample_variance(&self) -> Option<f64> {
+        (self.count > 1).then(|| self.m2 / (self.count - 1) as f64)
+    }
+
+    /// Population skewness, undefined for constant columns.
+    pub fn skewness(&self) -> Option<f64> {
+        (self.count > 0 && self.m2 > 0.)
+            .then(|| (self.count as f64).sqrt() * self.m3 / self.m2.powf(1.5))
+    }
+
+    /// Population excess kurtosis, undefined for constant columns.
+    pub fn kurtosis(&self) -> Option<f64> {
+        (self.count > 0 && self.m2 > 0.)
+            .then(|| self.count as f64 * self.m4 / (self.m2 * self.m2) - 3.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moments_empty() {
+        assert_eq!(Moments::default().mean(), None);
+    }
+
+    #[test]
+    fn test_moments_mean_does_not_overflow() {
+        let mut moments = Moments::default();
+        moments.add(f64::MAX);
+        moments.add(f64::MAX);
+        moments.add(f64::MAX);
+        assert_eq!(moments.mean(), Some(f64::MAX));
+    }
+
+    #[test]
+    fn test_moments_mean_against_exact() {
+        // exact reference: mean of 1e9 + k/4 for k in 0..4000 is 1e9 + 499.875
+        let mut moments = Moments::default();
+        for k in 0..4000 {
+            moments.add(1e9 + k as f64 / 4.);
+        }
+        let mean = moments.mean().unwrap();
+        assert!((mean - (1e9 + 499.875)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_moments_single_value() {
+        let mut moments = Moments::default();
+        moments.add(5.);
+        assert_eq!(moments.variance(), Some(0.));
+        assert_eq!(moments.sample_variance(), None);
+        assert_eq!(moments.skewness(), None);
+        assert_eq!(moments.kurtosis(), None);
+    }
+
+    #[test]
+    fn test_moments_against_two_pass() {
+        let values = [2., 8., -3., 4.5, 11., 0.25, 7., 7., -6.5, 3.];
+        let mut moments = Moments::default();
+        for value in values {
+            moments.add(value);
+        }
+
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let central = |power: i32| values.iter().map(|v| (v - mean).powi(power)).sum::<f64>();
+        let (m2, m3, m4) = (central(2), central(3), central(4));
+
+        let close = |actual: Option<f64>, expected: f64| {
+            assert!(
+                (actual.unwrap() - expected).abs() < 1e-12,
+                "{actual:?} != {expected}"
+            );
+        };
+        close(moments.mean(), mean);
+        close(moments.variance(), m2 / n);
+        close(moments.sample_variance(), m2 / (n - 1.));
+        close(moments.skewness(), n.sqrt() * m3 / m2.powf(1.5));
+        close(moments.kurtosis(), n * m4 / (m2 * m2) - 3.);
+    }
+}
@@ -0,0 +1,153 @@
+use std::{
+    cell::RefCell,
+    fmt,
+    fs::File,
+    io::{self, Read},
+    path::Path,
+    rc::Rc,
+    sync::{Arc, Mutex},
+};
+
+use csv::{ByteRecord, Position, Writer, WriterBuilder};
+
+use crate::{CsvColError, Dialect, Result, Terminator};
+
+/// Side CSV collecting rows that failed parsing.
+///
+/// The writer is shared between files parsed in parallel, each row holds
+/// its source, line number, the reason it was rejected and the record as it
+/// appeared in the input.
+#[derive(Clone)]
+pub struct RejectWriter {
+    writer: Arc<Mutex<Writer<File>>>,
+    source: String,
+}
+
+impl fmt::Debug for RejectWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RejectWriter")
+            .field("source", &self.source)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RejectWriter {
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .map_err(|e| CsvColError::Io(path.to_string_lossy().into_owned(), e))?;
+        let mut writer = WriterBuilder::new().flexible(true).from_writer(file);
+        writer
+            .write_record(["source", "line", "reason", "record"])
+            .map_err(CsvColError::Reject)?;
+
+        Ok(Self {
+            writer: Arc::new(Mutex::new(writer)),
+            source: String::new(),
+        })
+    }
+
+    /// Writer tagging rejected rows with `source`, sharing the same file.
+    pub fn for_source(&self, source: impl Into<String>) -> Self {
+        Self {
+            writer: self.writer.clone(),
+            source: source.into(),
+        }
+    }
+
+    pub fn write(&self, line: u64, reason: &str, record: &[u8]) -> Result<()> {
+        let mut row = ByteRecord::with_capacity(record.len() + 64, 4);
+        row.push_field(self.source.as_bytes());
+        row.push_field(line.to_string().as_bytes());
+        row.push_field(reason.as_bytes());
+        row.push_field(record);
+
+        let mut writer = self.writer.lock().map_err(|_| CsvColError::ThreadPanic)?;
+        writer.write_byte_record(&row).map_err(CsvColError::Reject)
+    }
+
+    pub fn flush(&self) -> Result<()> {
+        let mut writer = self.writer.lock().map_err(|_| CsvColError::ThreadPanic)?;
+        writer
+            .flush()
+            .map_err(|e| CsvColError::Reject(csv::Error::from(e)))
+    }
+}
+
+/// Input bytes kept around so rejected records can be written as they were
+/// read.
+///
+/// Only the bytes from the start of the current record on are kept, plus
+/// whatever the CSV reader buffered ahead of it.
+#[derive(Default)]
+pub(in crate::parser) struct RawInput {
+    /// Stream offset of `bytes[0]`.
+    offset: u64,
+    bytes: Vec<u8>,
+}
+
+impl RawInput {
+    /// Record read between `start` and the stream offset `end`, with its
+    /// line number.
+    ///
+    /// The CSV reader counts blank lines, comments and the `\n` of a `\r\n`
+    /// terminator as part of the following record, they are skipped here.
+    pub(in crate::parser) fn record(
+        &self,
+        start: &Position,
+        end: u64,
+        dialect: &Dialect,
+    ) -> (u64, &[u8]) {
+        let from = (start.byte().saturating_sub(self.offset) as usize).min(self.bytes.len());
+        let to = (end.saturating_sub(self.offset) as usize).clamp(from, self.bytes.len());
+        let mut raw = &self.bytes[from..to];
+        let is_terminator = |byte: u8| match dialect.terminator {
+            Terminator::Crlf => byte == b'\r' || byte == b'\n',
+            Terminator::Byte(terminator) => byte == terminator,
+        };
+
+        let mut line = start.line();
+        while let Some(&first) = raw.first() {
+            let skipped = if is_terminator(first) {
+                1
+            } else if dialect.comment == Some(first) {
+                raw.iter()
+                    .position(|&byte| is_terminator(byte))
+                    .map_or(raw.len(), |index| index + 1)
+            } else {
+                break;
+            };
+            line += raw[..skipped].iter().filter(|&&byte| byte == b'\n').count() as u64;
+            raw = &raw[skipped..];
+        }
+        while let Some((&last, rest)) = raw.split_last()
+            && is_terminator(last)
+        {
+            raw = rest;
+        }
+        (line, raw)
+    }
+
+    /// Forgets the bytes before the stream offset `end`.
+    pub(in crate::parser) fn discard(&mut self, end: u64) {
+        let count = (end.saturating_sub(self.offset) as usize).min(self.bytes.len());
+        self.bytes.drain(..count);
+        self.offset += count as u64;
+    }
+}
+
+/// Reader copying what it reads into a shared [`RawInput`], when there is
+/// one.
+pub(in crate::parser) struct Recording<R> {
+    pub(in crate::parser) inner: R,
+    pub(in crate::parser) raw: Option<Rc<RefCell<RawInput>>>,
+}
+
+impl<R: Read> Read for Recording<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.inner.read(buf)?;
+        if let Some(raw) = &self.raw {
+            raw.borrow_mut().bytes.extend_from_slice(&buf[..count]);
+        }
+        Ok(count)
+    }
+}
@@ -0,0 +1,276 @@
+use csv::ByteRecord;
+
+use crate::{
+    Dialect, DialectOverrides,
+    parser::{column::parse_number, file::reader_builder, trim_bytes},
+};
+
+/// Delimiters tried by the sniffer, in order of preference on ties.
+const DELIMITERS: [u8; 5] = [b',', b';', b'\t', b'|', b':'];
+
+/// Tokens commonly used for missing values in exported data.
+const NULL_CANDIDATES: [&str; 13] = [
+    "NULL", "null", "Null", "N/A", "n/a", "NA", "#N/A", "-", "\\N", "None", "nil", "NaN", "nan",
+];
+
+/// Dialect and null tokens inferred from the start of an input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sniffed {
+    pub dialect: Dialect,
+    /// Tokens found in otherwise numeric columns.
+    pub null_values: Vec<String>,
+}
+
+/// Infers the dialect of `sample`, the first bytes of an input.
+///
+/// `complete` tells whether `sample` holds the whole input, otherwise the
+/// last, possibly truncated, line is ignored. Only the delimiter, quote,
+/// escape, comment and header presence are inferred, settings in
+/// `overrides` are kept as given and used to detect the rest.
+pub fn sniff(sample: &[u8], complete: bool, overrides: &DialectOverrides) -> Sniffed {
+    let sample = match sample.iter().rposition(|&byte| byte == b'\n') {
+        Some(end) if !complete => &sample[..=end],
+        _ => sample,
+    };
+
+    let mut dialect = overrides.apply(Dialect {
+        comment: sample.starts_with(b"#").then_some(b'#'),
+        ..Dialect::default()
+    });
+    if overrides.delimiter.is_none() {
+        dialect.delimiter = sniff_delimiter(sample, &dialect);
+    }
+    if overrides.quote.is_none()
+        && count_quoted(sample, dialect.delimiter, b'\'')
+            > count_quoted(sample, dialect.delimiter, b'"')
+    {
+        dialect.quote = b'\'';
+        if overrides.delimiter.is_none() {
+            dialect.delimiter = sniff_delimiter(sample, &dialect);
+        }
+    }
+    if overrides.escape.is_none()
+        && overrides.double_quote.is_none()
+        && uses_escape(sample, dialect.quote)
+    {
+        dialect.escape = Some(b'\\');
+        dialect.double_quote = false;
+    }
+
+    let records = records(sample, &dialect);
+    if overrides.has_header.is_none() {
+        dialect.has_header = sniff_header(&records);
+    }
+    let data = if dialect.has_header {
+        records.get(1..).unwrap_or_default()
+    } else {
+        &records
+    };
+
+    Sniffed {
+        dialect,
+        null_values: sniff_null_values(data),
+    }
+}
+
+fn records(sample: &[u8], dialect: &Dialect) -> Vec<ByteRecord> {
+    reader_builder(dialect)
+        .has_headers(false)
+        .from_reader(sample)
+        .byte_records()
+        .map_while(|record| record.ok())
+        .collect()
+}
+
+/// Picks the delimiter giving the most rows with the same number of fields,
+/// preferring more fields on ties.
+fn sniff_delimiter(sample: &[u8], dialect: &Dialect) -> u8 {
+    let mut best = (dialect.delimiter, 0, 1);
+    for delimiter in DELIMITERS {
+        let records = records(
+            sample,
+            &Dialect {
+                delimiter,
+                ..*dialect
+            },
+        );
+        let mut lengths: Vec<usize> = records.iter().map(ByteRecord::len).collect();
+        lengths.sort_unstable();
+
+        let mut consistent = (0, 1);
+        for chunk in lengths.chunk_by(|a, b| a == b) {
+            consistent = consistent.max((chunk.len(), chunk[0]));
+        }
+        let (rows, fields) = consistent;
+        if fields > 1 && (rows, fields) > (best.1, best.2) {
+            best = (delimiter, rows, fields);
+        }
+    }
+    best.0
+}
+
+/// Number of fields starting with `quote`.
+fn count_quoted(sample: &[u8], delimiter: u8, quote: u8) -> usize {
+    let starts = sample
+        .windows(2)
+        .filter(|pair| matches!(pair[0], b'\n' | b'\r') || pair[0] == delimiter)
+        .filter(|pair| pair[1] == quote)
+        .count();
+    starts + usize::from(sample.first() == Some(&quote))
+}
+
+/// Quotes are escaped with a backslash and never doubled.
+fn uses_escape(sample: &[u8], quote: u8) -> bool {
+    let mut escaped = false;
+    for triple in sample.windows(3) {
+        match triple {
+            [b'\\', q, _] if *q == quote => escaped = true,
+            [previous, a, b] if *a == quote && *b == quote && *previous != b'\\' => return false,
+            _ => (),
+        }
+    }
+    escaped
+}
+
+fn is_null_candidate(field: &[u8]) -> bool {
+    field.is_empty() || NULL_CANDIDATES.iter().any(|t| t.as_bytes() == field)
+}
+
+fn is_numeric(field: &[u8]) -> bool {
+    !is_null_candidate(field) && parse_number(field).is_ok()
+}
+
+/// A header is assumed unless the first record looks like data, i.e. it is
+/// numeric in columns that are numeric in the following records.
+fn sniff_header(records: &[ByteRecord]) -> bool {
+    let Some((first, data)) = records.split_first() else {
+        return true;
+    };
+
+    let mut votes = 0i64;
+    for (index, field) in first.iter().enumerate() {
+        let mut values = data
+            .iter()
+            .filter_map(|record| record.get(index))
+            .map(trim_bytes)
+            .filter(|field| !is_null_candidate(field))
+            .peekable();
+        if values.peek().is_none() || !values.all(is_numeric) {
+            continue;
+        }
+        votes += if is_numeric(trim_bytes(field)) { -1 } else { 1 };
+    }
+    votes >= 0
+}
+
+/// Null candidates appearing in columns whose other values are numeric.
+fn sniff_null_values(data: &[ByteRecord]) -> Vec<String> {
+    let columns = data.iter().map(ByteRecord::len).max().unwrap_or_default();
+    let mut found = [false; NULL_CANDIDATES.len()];
+    for index in 0..columns {
+        let fields = || {
+            data.iter()
+                .filter_map(|record| record.get(index))
+                .map(trim_bytes)
+        };
+        if !fields().any(is_numeric)
+            || !fields().all(|field| is_numeric(field) || is_null_candidate(field))
+        {
+            continue;
+        }
+        for field in fields() {
+            if let Some(position) = NULL_CANDIDATES.iter().position(|t| t.as_bytes() == field) {
+                found[position] = true;
+            }
+        }
+    }
+
+    NULL_CANDIDATES
+        .iter()
+        .zip(found)
+        .filter(|(_, found)| *found)
+        .map(|(token, _)| token.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_default_dialect() {
+        let sniffed = sniff(
+            b"id,a,b\n1,10,20\n2,30,40\n",
+            true,
+            &DialectOverrides::default(),
+        );
+        assert_eq!(sniffed.dialect, Dialect::default());
+        assert!(sniffed.null_values.is_empty());
+    }
+
+    #[test]
+    fn test_sniff_delimiter_and_quote() {
+        let sniffed = sniff(
+            b"id;name;a\n1;'x, y';10\n2;'z';20\n3;'w';30\n",
+            true,
+            &DialectOverrides::default(),
+        );
+        assert_eq!(sniffed.dialect.delimiter, b';');
+        assert_eq!(sniffed.dialect.quote, b'\'');
+        assert!(sniffed.dialect.has_header);
+    }
+
+    #[test]
+    fn test_sniff_tab_ignores_truncated_line() {
+        let sniffed = sniff(
+            b"a\tb\n1\t2\n3\t4\n5,6",
+            false,
+            &DialectOverrides::default(),
+        );
+        assert_eq!(sniffed.dialect.delimiter, b'\t');
+    }
+
+    #[test]
+    fn test_sniff_escape_and_comment() {
+        let sniffed = sniff(
+            b"# export\nname,a\n\"say \\\"hi\\\"\",1\n\"x\",2\n",
+            true,
+            &DialectOverrides::default(),
+        );
+        assert_eq!(sniffed.dialect.comment, Some(b'#'));
+        assert_eq!(sniffed.dialect.escape, Some(b'\\'));
+        assert!(!sniffed.dialect.double_quote);
+    }
+
+    #[test]
+    fn test_sniff_no_header() {
+        let sniffed = sniff(
+            b"1,10,20\n2,30,40\n3,50,60\n",
+            true,
+            &DialectOverrides::default(),
+        );
+        assert!(!sniffed.dialect.has_header);
+    }
+
+    #[test]
+    fn test_sniff_null_values() {
+        let sniffed = sniff(
+            b"name,a,b\nNA,1,-\nx,NULL,2\ny,\\N,3\n",
+            true,
+            &DialectOverrides::default(),
+        );
+        assert!(sniffed.dialect.has_header);
+        assert_eq!(sniffed.null_values, vec!["NULL", "-", "\\N"]);
+    }
+
+    #[test]
+    fn test_sniff_overrides() {
+        let overrides = DialectOverrides {
+            delimiter: Some(b','),
+            ..DialectOverrides::default()
+        };
+        let sniffed = sniff(b"name;a\nx;1\ny;-\n", true, &overrides);
+        assert_eq!(sniffed.dialect.delimiter, b',');
+        assert!(sniffed.null_values.is_empty());
+    }
+}
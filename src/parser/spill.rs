@@ -0,0 +1,246 @@
+use std::{
+    cmp::Reverse,
+    collections::BinaryHeap,
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+};
+
+use crate::parser::median::{OrderedValue, interpolate_quantiles};
+
+/// Number of runs merged at once.
+const MERGE_FAN_IN: usize = 16;
+
+/// Exact quantiles for columns that don't fit in the memory budget.
+///
+/// Values are buffered up to `capacity`, then sorted and written to an
+/// anonymous temp file as a run. Whenever `MERGE_FAN_IN` runs of the same
+/// tier pile up they are merged into a single run of the next tier, so at
+/// most `MERGE_FAN_IN - 1` runs per tier stay open and the number of open
+/// files and read buffers only grows with the logarithm of the input size.
+/// Queries k-way merge all runs with the in-memory tail.
+pub struct SpillingQuantiles {
+    capacity: usize,
+    buffer: Vec<f64>,
+    runs: Vec<Run>,
+    count: u64,
+    // I/O errors can't be reported per value, so the first one is kept
+    // and returned from the next query
+    error: Option<io::Error>,
+}
+
+/// Sorted values spilled to disk, `tier` is the number of merges they went
+/// through.
+struct Run {
+    file: File,
+    tier: u32,
+}
+
+enum Source<'a> {
+    Run(BufReader<&'a File>),
+    Tail(std::vec::IntoIter<f64>),
+}
+
+impl Source<'_> {
+    fn next(&mut self) -> io::Result<Option<f64>> {
+        match self {
+            Self::Run(reader) => {
+                let mut bytes = [0; size_of::<f64>()];
+                match reader.read_exact(&mut bytes) {
+                    Ok(()) => Ok(Some(f64::from_le_bytes(bytes))),
+                    Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+                    Err(e) => Err(e),
+                }
+            }
+            Self::Tail(values) => Ok(values.next()),
+        }
+    }
+}
+
+/// K-way merge of sorted runs and an in-memory tail.
+struct Merge<'a> {
+    sources: Vec<Source<'a>>,
+    heap: BinaryHeap<Reverse<(OrderedValue, usize)>>,
+}
+
+impl<'a> Merge<'a> {
+    fn new(runs: &'a [Run], tail: Vec<f64>) -> io::Result<Self> {
+        let mut sources = Vec::with_capacity(runs.len() + 1);
+        for run in runs {
+            let mut file = &run.file;
+            file.seek(SeekFrom::Start(0))?;
+            sources.push(Source::Run(BufReader::new(file)));
+        }
+        sources.push(Source::Tail(tail.into_iter()));
+
+        let mut heap = BinaryHeap::with_capacity(sources.len());
+        for (index, source) in sources.iter_mut().enumerate() {
+            if let Some(value) = source.next()? {
+                heap.push(Reverse((OrderedValue(value), index)));
+            }
+        }
+        Ok(Self { sources, heap })
+    }
+
+    fn next(&mut self) -> io::Result<Option<f64>> {
+        let Some(Reverse((value, index))) = self.heap.pop() else {
+            return Ok(None);
+        };
+        if let Some(next) = self.sources[index].next()? {
+            self.heap.push(Reverse((OrderedValue(next), index)));
+        }
+        Ok(Some(value.0))
+    }
+}
+
+fn write_run(values: impl IntoIterator<Item = io::Result<f64>>) -> io::Result<File> {
+    let mut writer = BufWriter::new(tempfile::tempfile()?);
+    for value in values {
+        writer.write_all(&value?.to_le_bytes())?;
+    }
+    writer.into_inner().map_err(|e| e.into_error())
+}
+
+impl SpillingQuantiles {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            buffer: Vec::new(),
+            runs: Vec::new(),
+            count: 0,
+            error: None,
+        }
+    }
+
+    pub fn add(&mut self, value: f64) {
+        if self.error.is_some() {
+            return;
+        }
+
+        self.buffer.push(value);
+        self.count += 1;
+        if self.buffer.len() >= self.capacity
+            && let Err(e) = self.spill()
+        {
+            self.error = Some(e);
+        }
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        self.buffer.sort_unstable_by(f64::total_cmp);
+
+        let file = write_run(self.buffer.drain(..).map(Ok))?;
+        self.runs.push(Run { file, tier: 0 });
+        self.compact()
+    }
+
+    /// Merges the last `MERGE_FAN_IN` runs into one of the next tier as long
+    /// as they share a tier. Tiers never increase along `runs`, so this
+    /// works like carrying in a base `MERGE_FAN_IN` counter.
+    fn compact(&mut self) -> io::Result<()> {
+        while let Some(start) = self.runs.len().checked_sub(MERGE_FAN_IN) {
+            let tier = self.runs[start].tier;
+            if self.runs[start..].iter().any(|run| run.tier != tier) {
+                break;
+            }
+
+            let mut merge = Merge::new(&self.runs[start..], Vec::new())?;
+            let file = write_run(std::iter::from_fn(|| merge.next().transpose()))?;
+            self.runs.truncate(start);
+            self.runs.push(Run {
+                file,
+                tier: tier + 1,
+            });
+        }
+        Ok(())
+    }
+
+    /// Merges all runs and returns values at the zero-based `ranks`, which
+    /// must be sorted.
+    fn values_at(&self, ranks: &[u64]) -> io::Result<Vec<f64>> {
+        let mut tail = self.buffer.clone();
+        tail.sort_unstable_by(f64::total_cmp);
+
+        let mut merge = Merge::new(&self.runs, tail)?;
+        let mut values = Vec::with_capacity(ranks.len());
+        let mut wanted = ranks.iter().peekable();
+        let mut rank = 0;
+        while wanted.peek().is_some()
+            && let Some(value) = merge.next()?
+        {
+            while wanted.next_if_eq(&&rank).is_some() {
+                values.push(value);
+            }
+            rank += 1;
+        }
+        Ok(values)
+    }
+
+    /// Exact quantiles for every `q` in `quantiles`, found in one merge pass.
+    pub fn quantiles(&self, quantiles: &[f64]) -> io::Result<Vec<Option<f64>>> {
+        if let Some(e) = &self.error {
+            return Err(io::Error::new(e.kind(), e.to_string()));
+        }
+        interpolate_quantiles(quantiles, self.count, |ranks| self.values_at(ranks))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spilling_quantiles_empty() {
+        let values = SpillingQuantiles::new(4);
+        assert_eq!(values.quantiles(&[0.5]).unwrap(), vec![None]);
+    }
+
+    #[test]
+    fn test_spilling_quantiles_match_sorted() {
+        let mut values = SpillingQuantiles::new(7);
+        let mut reference: Vec<f64> = (0..100).map(|v| ((v * 37) % 101) as f64 - 50.).collect();
+        for value in &reference {
+            values.add(*value);
+        }
+        assert_eq!(values.runs.len(), 14);
+        assert_eq!(values.buffer.len(), 2);
+
+        reference.sort_unstable_by(f64::total_cmp);
+        let result = values.quantiles(&[0., 0.5, 0.25, 1.]).unwrap();
+        assert_eq!(
+            result,
+            vec![
+                Some(reference[0]),
+                Some((reference[49] + reference[50]) / 2.),
+                Some(reference[24] + (reference[25] - reference[24]) * 0.75),
+                Some(reference[99]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spilling_quantiles_bounded_runs() {
+        let mut values = SpillingQuantiles::new(1);
+        for value in (0..300).rev() {
+            values.add(value as f64);
+        }
+
+        // 300 single value runs are 1 * 16^2 + 2 * 16 + 12 in base 16
+        let tiers: Vec<_> = values.runs.iter().map(|run| run.tier).collect();
+        assert_eq!(tiers.len(), 15);
+        assert_eq!(tiers[..3], [2, 1, 1]);
+        assert_eq!(
+            values.quantiles(&[0., 0.5, 1.]).unwrap(),
+            vec![Some(0.), Some(149.5), Some(299.)]
+        );
+    }
+
+    #[test]
+    fn test_spilling_quantiles_repeated_query() {
+        let mut values = SpillingQuantiles::new(2);
+        for value in [5., 1., 3., 2., 4.] {
+            values.add(value);
+        }
+        assert_eq!(values.quantiles(&[0.5]).unwrap(), vec![Some(3.)]);
+        assert_eq!(values.quantiles(&[0.5]).unwrap(), vec![Some(3.)]);
+    }
+}
@@ -0,0 +1,148 @@
+/// Running sum of a column used to calculate the mean.
+///
+/// Integer columns are accumulated in `i128`, which can't overflow for any
+/// realistic number of `i64` values. If it ever does, or if the column is
+/// promoted to floating point, the sum continues as `f64` using Neumaier
+/// compensated summation to keep rounding error from accumulating.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sum {
+    Int(i128),
+    Float { sum: f64, compensation: f64 },
+}
+
+impl Default for Sum {
+    fn default() -> Self {
+        Self::Int(0)
+    }
+}
+
+impl Sum {
+    pub fn is_int(&self) -> bool {
+        matches!(self, Self::Int(_))
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            Self::Int(value) => *value as f64,
+            Self::Float { sum, compensation } => sum + compensation,
+        }
+    }
+
+    /// Switches to floating point accumulation.
+    pub fn promote(&mut self) {
+        *self = Self::Float {
+            sum: self.as_f64(),
+            compensation: 0.,
+        };
+    }
+
+    /// Adds an integer value.
+    ///
+    /// Returns `false` if the integer sum overflowed and the accumulator had
+    /// to fall back to `f64`, losing precision.
+    pub fn add_int(&mut self, value: i64) -> bool {
+        match self {
+            Self::Int(sum) => match sum.checked_add(value as i128) {
+                Some(result) => {
+                    *sum = result;
+                    true
+                }
+                None => {
+                    self.promote();
+                    self.add_float(value as f64);
+                    false
+                }
+            },
+            Self::Float { .. } => self.add_float(value as f64),
+        }
+    }
+
+    /// Adds a floating point value.
+    ///
+    /// Returns `false` if the sum is no longer finite.
+    pub fn add_float(&mut self, value: f64) -> bool {
+        if self.is_int() {
+            self.promote();
+        }
+        let Self::Float { sum, compensation } = self else {
+            unreachable!("sum was promoted to float");
+        };
+        let total = *sum + value;
+        if sum.abs() >= value.abs() {
+            *compensation += (*sum - total) + value;
+        } else {
+            *compensation += (value - total) + *sum;
+        }
+        *sum = total;
+        sum.is_finite()
+    }
+
+    /// Mean of `count` accumulated values, `None` if the sum is not finite.
+    pub fn mean(&self, count: usize) -> Option<f64> {
+        let sum = self.as_f64();
+        if count == 0 || !sum.is_finite() {
+            return None;
+        }
+        Some(sum / count as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sum_int_beyond_i64() {
+        let mut sum = Sum::default();
+        assert!(sum.add_int(i64::MAX));
+        assert!(sum.add_int(i64::MAX));
+        assert_eq!(sum, Sum::Int(2 * i64::MAX as i128));
+    }
+
+    #[test]
+    fn test_sum_int_overflow_falls_back_to_float() {
+        let mut sum = Sum::Int(i128::MAX);
+        assert!(!sum.add_int(1));
+        assert!(!sum.is_int());
+        assert_eq!(sum.as_f64(), i128::MAX as f64);
+    }
+
+    #[test]
+    fn test_sum_float_overflow() {
+        let mut sum = Sum::default();
+        assert!(sum.add_float(f64::MAX));
+        assert!(!sum.add_float(f64::MAX));
+        assert_eq!(sum.mean(2), None);
+    }
+
+    #[test]
+    fn test_sum_float_cancellation() {
+        let mut sum = Sum::default();
+        for value in [1e100, 1.0, -1e100] {
+            sum.add_float(value);
+        }
+        assert_eq!(sum.as_f64(), 1.0);
+    }
+
+    #[test]
+    fn test_sum_float_small_terms() {
+        // exact reference: 1 + 2^-60 * 2^20 = 1 + 2^-40
+        let mut sum = Sum::default();
+        sum.add_float(1.0);
+        for _ in 0..1 << 20 {
+            sum.add_float(2f64.powi(-60));
+        }
+        assert_eq!(sum.as_f64(), 1.0 + 2f64.powi(-40));
+    }
+
+    #[test]
+    fn test_sum_float_repeated_decimal() {
+        // 0.1 is not representable, naive summation drifts to 100000.00000133288
+        let mut sum = Sum::default();
+        for _ in 0..1_000_000 {
+            sum.add_float(0.1);
+        }
+        assert_eq!(sum.as_f64(), 100000.0);
+        assert_eq!(sum.mean(1_000_000), Some(0.1));
+    }
+}
@@ -0,0 +1,198 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::{Output, Result, Rounding, Stats};
+
+/// Version of the report schema, bumped on every incompatible change to the
+/// serialized [`Report`].
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Results of all processed files, in the order they were given.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub schema_version: u32,
+    pub files: Vec<FileReport>,
+}
+
+impl Report {
+    pub fn new(files: Vec<FileReport>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            files,
+        }
+    }
+}
+
+/// Result of a single file, either its [`Output`] or the error that stopped
+/// parsing.
+#[derive(Debug, Serialize)]
+pub struct FileReport {
+    /// Path as given on the command line, `-` for standard input.
+    pub path: String,
+    pub error: Option<String>,
+    #[serde(flatten)]
+    pub output: Option<Output>,
+    /// Wall time spent parsing, left out of serialized reports so they stay
+    /// reproducible.
+    #[serde(skip)]
+    pub elapsed: Duration,
+}
+
+impl FileReport {
+    pub fn new(path: String, result: Result<Output>) -> Self {
+        match result {
+            Ok(output) => Self {
+                path,
+                error: None,
+                output: Some(output),
+                elapsed: Duration::ZERO,
+            },
+            Err(e) => Self {
+                path,
+                error: Some(e.to_string()),
+                output: None,
+                elapsed: Duration::ZERO,
+            },
+        }
+    }
+
+    /// Rounds the statistics of every column.
+    pub fn round(&mut self, rounding: Rounding) {
+        if let Some(output) = &mut self.output {
+            for (_, stats) in output.columns.iter_mut() {
+                stats.round(rounding);
+            }
+        }
+    }
+
+    /// Statistics of every column tagged with this file, in header order.
+    pub fn column_records(&self) -> impl Iterator<Item = ColumnRecord<'_>> {
+        self.output
+            .iter()
+            .flat_map(|output| output.columns.iter())
+            .map(|(column, stats)| ColumnRecord {
+                file: &self.path,
+                column,
+                stats,
+            })
+    }
+}
+
+/// Statistics of a single column with the file it belongs to, the unit of
+/// line oriented formats.
+#[derive(Debug, Serialize)]
+pub struct ColumnRecord<'a> {
+    pub file: &'a str,
+    pub column: &'a str,
+    #[serde(flatten)]
+    pub stats: &'a Stats,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        CsvColError,
+        Number::{Float, Int},
+        Quantile,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_report_json() {
+        let output = Output {
+            columns: [
+                (
+                    "b".to_string(),
+                    Stats {
+                        count: 1,
+                        min: Some(Int(2)),
+                        ..Stats::default()
+                    },
+                ),
+                ("a".to_string(), Stats::default()),
+            ]
+            .into_iter()
+            .collect(),
+            ..Output::default()
+        };
+        let report = Report::new(vec![
+            FileReport::new("ok.csv".to_string(), Ok(output)),
+            FileReport::new("bad.csv".to_string(), Err(CsvColError::ThreadPanic)),
+        ]);
+
+        let value = serde_json::to_value(&report).unwrap();
+
+        assert_eq!(value["schema_version"], json!(SCHEMA_VERSION));
+        let files = value["files"].as_array().unwrap();
+        assert_eq!(files[0]["path"], json!("ok.csv"));
+        assert_eq!(files[0]["error"], json!(null));
+        assert_eq!(files[0]["dialect"]["delimiter"], json!(","));
+        assert_eq!(files[0]["rows"], json!(0));
+        assert_eq!(files[0]["columns"][0]["name"], json!("b"));
+        assert_eq!(files[0]["columns"][0]["min"], json!(2));
+        assert_eq!(files[0]["columns"][1]["name"], json!("a"));
+        assert_eq!(files[1]["error"], json!("Thread paniced"));
+        assert!(files[1].get("columns").is_none());
+    }
+
+    #[test]
+    fn test_round() {
+        let stats = Stats {
+            min: Some(Float(0.123456)),
+            max: Some(Int(123456)),
+            mean: Some(2. / 3.),
+            median: Some(12345.678),
+            quantiles: vec![Quantile {
+                quantile: 0.5,
+                value: Some(-0.0049),
+            }],
+            ..Stats::default()
+        };
+        let output = Output {
+            columns: [("a".to_string(), stats)].into_iter().collect(),
+            ..Output::default()
+        };
+
+        let mut decimals = FileReport::new("a.csv".to_string(), Ok(output));
+        decimals.round(Rounding::Decimals(2));
+        let stats = decimals.output.as_ref().unwrap().columns.get("a").unwrap();
+        assert_eq!(stats.min, Some(Float(0.12)));
+        assert_eq!(stats.max, Some(Int(123456)));
+        assert_eq!(stats.mean, Some(0.67));
+        assert_eq!(stats.median, Some(12345.68));
+        // `-0. == 0.`, so the sign is checked on its own
+        let quantile = stats.quantiles[0].value.unwrap();
+        assert_eq!(quantile, 0.);
+        assert!(quantile.is_sign_positive());
+
+        let mut significant = decimals;
+        significant.round(Rounding::Significant(3));
+        let stats = significant.output.unwrap().columns.remove("a").unwrap();
+        assert_eq!(stats.mean, Some(0.67));
+        assert_eq!(stats.median, Some(12300.));
+    }
+
+    #[test]
+    fn test_column_records() {
+        let output = Output {
+            columns: [("a".to_string(), Stats::default())].into_iter().collect(),
+            ..Output::default()
+        };
+        let file = FileReport::new("ok.csv".to_string(), Ok(output));
+
+        let lines: Vec<_> = file
+            .column_records()
+            .map(|record| serde_json::to_value(record).unwrap())
+            .collect();
+
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["file"], json!("ok.csv"));
+        assert_eq!(lines[0]["column"], json!("a"));
+        assert_eq!(lines[0]["count"], json!(0));
+        assert_eq!(lines[0]["precision_loss"], json!(false));
+    }
+}